@@ -1,14 +1,14 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use rume::{Processor, Renderable};
+use rume::Processor;
 
 pub mod synth {
     rume::graph! {
         inputs: {
-            freq: { init: 220.0, range: 64.0..880.0, smooth: 10 },
-            amp:  { init:   0.1, range:  0.0..0.8,   smooth: 10 },
+            freq: { init: 0.5,  range:  64.0..880.0, taper: log, smooth: 10 },
+            amp:  { init: 0.75, range: -80.0..0.0,   taper: db,  smooth: 10 },
         },
         outputs: {
-            out,
+            out: { channels: config },
         },
         processors: {
             sine: rume::Sine::new(),
@@ -16,7 +16,8 @@ pub mod synth {
         connections: {
             freq.output  ->  sine.input.0,
             amp.output   ->  sine.input.1,
-            sine.output  ->  out.input,
+            sine.output  ->  out.input.0,
+            sine.output  ->  out.input.1,
         }
     }
 }
@@ -29,7 +30,11 @@ fn main() {
     let config = device.default_output_config().unwrap();
 
     let (mut graph, _, outputs) = synth::build();
-    graph.prepare((config.sample_rate().0 as f32).into());
+    graph.prepare(rume::AudioConfig {
+        sample_rate: config.sample_rate().0 as usize,
+        buffer_size: 512,
+        num_channels: config.channels() as usize,
+    });
 
     match config.sample_format() {
         cpal::SampleFormat::F32 => run::<f32>(&device, &config.into(), graph, outputs).unwrap(),
@@ -42,22 +47,19 @@ fn run<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     mut graph: rume::SignalChain,
-    mut graph_outputs: synth::Outputs,
+    graph_outputs: synth::Outputs,
 ) -> Result<(), anyhow::Error>
 where
     T: cpal::Sample,
 {
-    let channels = config.channels as usize;
-
-    let mut next_value = move || {
-        graph.render(1);
-        graph_outputs.out.dequeue().unwrap()
-    };
+    let mut buffer = Vec::new();
 
     let stream = device.build_output_stream(
         config,
         move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            write_data(data, channels, &mut next_value)
+            buffer.resize(data.len(), 0.0);
+            graph.render_into(&graph_outputs.out, &mut buffer);
+            write_data(data, &buffer)
         },
         |err| eprintln!("an error occurred on stream: {}", err),
     )?;
@@ -68,14 +70,11 @@ where
     Ok(())
 }
 
-fn write_data<T>(output: &mut [T], channels: usize, next_sample: &mut dyn FnMut() -> f32)
+fn write_data<T>(output: &mut [T], samples: &[f32])
 where
     T: cpal::Sample,
 {
-    for frame in output.chunks_mut(channels) {
-        let value: T = cpal::Sample::from::<f32>(&next_sample());
-        for sample in frame.iter_mut() {
-            *sample = value;
-        }
+    for (sample, value) in output.iter_mut().zip(samples) {
+        *sample = cpal::Sample::from::<f32>(value);
     }
 }
@@ -10,20 +10,21 @@ const NUM_SAMPLES: usize = SAMPLE_RATE as usize * NUM_SECONDS;
 const NUM_BUFFERS: usize = (SAMPLE_RATE as f32 / BUFFER_SIZE as f32) as usize * NUM_SECONDS;
 const BUFFER_TIME: f32 = BUFFER_SIZE as f32 / SAMPLE_RATE as f32;
 
-#[rume::processor]
-pub struct Lpf {
-    #[input]
-    previous: f32,
-
-    #[output]
-    current: f32,
-}
-
-impl Processor for Lpf {
-    fn prepare(&mut self, _: AudioConfig) {}
-    fn process(&mut self) {
-        self.current = (self.previous + self.current) * 0.5;
-        self.previous = self.current;
+pub mod filter {
+    rume::graph! {
+        inputs: {
+            audio_in,
+        },
+        outputs: {
+            audio_out,
+        },
+        processors: {
+            lpf: rume::Biquad::new(rume::BiquadKind::Lowpass),
+        },
+        connections: {
+            audio_in.output ->  lpf.input.0,
+            lpf.output      ->  audio_out.input,
+        }
     }
 }
 
@@ -32,22 +33,22 @@ pub mod synth {
 
     rume::graph! {
         inputs: {
-            freq,
+            freq: { overflow: drop_oldest },
         },
         outputs: {
-            audio_out,
+            audio_out: { capacity: 64, sample: i16 },
         },
         processors: {
             lvl: rume::Value::new(0.6),
             amt: rume::Value::new(0.1),
             sine: rume::Saw::default(),
-            lpf: Lpf::default(),
+            lpf: filter::subgraph(),
         },
         connections: {
-            freq.output ->  sine.input.0,
-            lvl.output  ->  sine.input.1,
-            sine.output ->  lpf.input,
-            lpf.output  ->  audio_out.input,
+            freq.output  ->  sine.input.0,
+            lvl.output   ->  sine.input.1,
+            sine.output  ->  lpf.input.0,
+            lpf.output.0 ->  audio_out.input,
         }
     }
 }
@@ -58,7 +59,7 @@ fn main() {
     // UI Thread
     std::thread::spawn(move || {
         for i in (110..440).step_by(2) {
-            params.freq.enqueue(i as f32).unwrap();
+            params.freq.push(i as f32);
             std::thread::sleep(std::time::Duration::from_millis(10));
         }
         println!("done modulating");
@@ -71,8 +72,8 @@ fn main() {
         let spec = hound::WavSpec {
             channels: 1,
             sample_rate: SAMPLE_RATE,
-            bits_per_sample: 32,
-            sample_format: hound::SampleFormat::Float,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
         };
         let mut writer = hound::WavWriter::create("test.wav", spec).unwrap();
 
@@ -82,7 +83,7 @@ fn main() {
                 writer.write_sample(sample).unwrap();
             }
         }
-        println!("done rendering");
+        println!("done rendering, {} overflows", outs.audio_out.overflows());
     })
     .join();
 }
@@ -3,11 +3,14 @@
 pub use rume_core::*;
 pub use rume_macros::*;
 
-#[cfg(feature = "std")]
 pub mod processors;
+pub use processors::*;
 
 #[cfg(feature = "std")]
-pub use processors::*;
+pub mod patch;
+
+#[cfg(feature = "std")]
+pub use patch::*;
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
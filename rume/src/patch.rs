@@ -0,0 +1,947 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// The parameters a processor is
+/// constructed with, by name.
+pub type Params = BTreeMap<String, Param>;
+
+/// The value of a parameter: a number, or
+/// the name of a choice such as the curve
+/// of an envelope.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Param {
+    Number(f32),
+    Name(String),
+}
+
+impl Param {
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Param::Number(number) => Some(*number),
+            Param::Name(_) => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Param::Number(_) => None,
+            Param::Name(name) => Some(name),
+        }
+    }
+}
+
+/// A graph described as data rather than
+/// code, so that a patch can be changed
+/// without recompiling. It is built into
+/// a `SignalChain` by a `ProcessorRegistry`.
+///
+/// ```
+///     use rume::*;
+///
+///     let description = GraphDescription::from_json(r#"{
+///         "inputs": [{ "name": "freq", "init": 220.0, "range": [64.0, 880.0], "smooth": 10 }],
+///         "outputs": ["out"],
+///         "processors": [{ "name": "sine", "type": "rume::Sine", "params": { "amplitude": 0.5 } }],
+///         "connections": [
+///             { "from": "freq", "to": "sine", "input": 0 },
+///             { "from": "sine", "to": "out" }
+///         ]
+///     }"#).unwrap();
+///
+///     let (mut chain, mut endpoints) = ProcessorRegistry::default().build(&description).unwrap();
+///
+///     chain.prepare(48_000.into());
+///     chain.render(64);
+///     assert!(endpoints.outputs.get_mut("out").unwrap().dequeue().is_some());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphDescription {
+    #[serde(default)]
+    pub inputs: Vec<InputDescription>,
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    #[serde(default)]
+    pub processors: Vec<ProcessorDescription>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionDescription>,
+}
+
+/// An input endpoint and the options
+/// of its `InputEndpointBuilder`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputDescription {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<InputKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smooth: Option<f32>,
+}
+
+/// The `InputEndpointKind` of an input,
+/// as it is written in a description.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputKind {
+    Follow,
+    Trigger,
+    Pulse(u32),
+    #[serde(rename = "pulse_ms")]
+    PulseMillis(f32),
+    Gate,
+    Toggle,
+    Latch,
+    #[serde(rename = "sample_and_hold")]
+    SampleAndHold,
+}
+
+impl From<InputKind> for InputEndpointKind {
+    fn from(kind: InputKind) -> Self {
+        match kind {
+            InputKind::Follow => InputEndpointKind::Follow,
+            InputKind::Trigger => InputEndpointKind::Trigger,
+            InputKind::Pulse(samples) => InputEndpointKind::Pulse(samples),
+            InputKind::PulseMillis(ms) => InputEndpointKind::PulseMillis(ms),
+            InputKind::Gate => InputEndpointKind::Gate,
+            InputKind::Toggle => InputEndpointKind::Toggle,
+            InputKind::Latch => InputEndpointKind::Latch,
+            InputKind::SampleAndHold => InputEndpointKind::SampleAndHold,
+        }
+    }
+}
+
+/// A processor, by the name it is connected
+/// with and the name its type is registered
+/// under in the `ProcessorRegistry`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessorDescription {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: Params,
+}
+
+/// A connection from an output of one processor
+/// or input endpoint to an input of another
+/// processor or output endpoint. Ports are
+/// given by index and default to the first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionDescription {
+    pub from: String,
+    #[serde(default)]
+    pub output: usize,
+    pub to: String,
+    #[serde(default)]
+    pub input: usize,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub feedback: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl GraphDescription {
+    pub fn from_json(json: &str) -> Result<Self, PatchError> {
+        serde_json::from_str(json).map_err(|error| PatchError::Parse(error.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// The reasons a `GraphDescription`
+/// can fail to load or to build.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The description is not valid JSON
+    /// or does not describe a graph.
+    Parse(String),
+    /// A processor's type is not
+    /// in the registry.
+    UnknownType(String),
+    /// A connection refers to a processor
+    /// or endpoint that is not described.
+    UnknownName(String),
+    /// Two processors or endpoints
+    /// share the same name.
+    DuplicateName(String),
+    /// A connection uses an input the
+    /// processor does not have.
+    UnknownInput { processor: String, port: usize },
+    /// A connection uses an output the
+    /// processor does not have.
+    UnknownOutput { processor: String, port: usize },
+    /// A processor's factory rejected
+    /// the parameters it was given.
+    InvalidParams { processor: String, reason: String },
+    /// The described graph cannot be built.
+    Graph(GraphError),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::Parse(error) => write!(f, "the graph description is invalid: {}", error),
+            PatchError::UnknownType(kind) => {
+                write!(f, "no processor type is registered as {}", kind)
+            }
+            PatchError::UnknownName(name) => write!(f, "nothing in the graph is named {}", name),
+            PatchError::DuplicateName(name) => {
+                write!(f, "more than one thing in the graph is named {}", name)
+            }
+            PatchError::UnknownInput { processor, port } => {
+                write!(f, "{} has no input {}", processor, port)
+            }
+            PatchError::UnknownOutput { processor, port } => {
+                write!(f, "{} has no output {}", processor, port)
+            }
+            PatchError::InvalidParams { processor, reason } => {
+                write!(f, "the parameters of {} are invalid: {}", processor, reason)
+            }
+            PatchError::Graph(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl From<GraphError> for PatchError {
+    fn from(error: GraphError) -> Self {
+        PatchError::Graph(error)
+    }
+}
+
+/// The streams of the endpoints of a graph
+/// built from a description, by name.
+#[derive(Default)]
+pub struct PatchEndpoints {
+    pub inputs: HashMap<String, InputStreamProducer>,
+    pub outputs: HashMap<String, OutputStreamConsumer>,
+}
+
+/// The ports of a processor added to a
+/// chain being built from a description.
+struct PatchNode {
+    input: Box<dyn Fn(usize) -> Option<InputPort>>,
+    output: Box<dyn Fn(usize) -> Option<OutputPort>>,
+}
+
+impl PatchNode {
+    fn of<P: IndexedPorts + 'static>(processor: Handle<P>) -> Self {
+        Self {
+            input: Box::new(move |index| P::input_port(processor, index)),
+            output: Box::new(move |index| P::output_port(processor, index)),
+        }
+    }
+}
+
+type Factory = Box<dyn Fn(&mut SignalChainBuilder, &Params) -> Result<PatchNode, String>>;
+
+/// Maps the type names used in a `GraphDescription`
+/// to factories that construct those processors
+/// from their parameters. The default registry
+/// knows `rume::Sine`, `rume::Saw`, `rume::Square`,
+/// `rume::Pulse`, `rume::Triangle`, `rume::Value`,
+/// `rume::Svf`, `rume::Adsr`, `rume::Lfo` and the
+/// biquads, e.g. `rume::Lowpass`.
+pub struct ProcessorRegistry {
+    factories: HashMap<String, Factory>,
+}
+
+impl Default for ProcessorRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register("rume::Value", &["value"], |params| {
+            Ok(Value::new(number(params, "value")?.unwrap_or_default()))
+        });
+
+        registry.register("rume::Sine", &["frequency", "amplitude"], |params| {
+            let mut sine = Sine::new();
+            if let Some(frequency) = number(params, "frequency")? {
+                SinefrequencyInput.set(&mut sine, frequency);
+            }
+            if let Some(amplitude) = number(params, "amplitude")? {
+                SineamplitudeInput.set(&mut sine, amplitude);
+            }
+            Ok(sine)
+        });
+
+        registry.register("rume::Saw", &["frequency", "amplitude"], |params| {
+            let mut saw = Saw::default();
+            if let Some(frequency) = number(params, "frequency")? {
+                SawfrequencyInput.set(&mut saw, frequency);
+            }
+            if let Some(amplitude) = number(params, "amplitude")? {
+                SawamplitudeInput.set(&mut saw, amplitude);
+            }
+            Ok(saw)
+        });
+
+        registry.register("rume::Square", &["frequency", "amplitude"], |params| {
+            let mut square = Square::new();
+            if let Some(frequency) = number(params, "frequency")? {
+                SquarefrequencyInput.set(&mut square, frequency);
+            }
+            if let Some(amplitude) = number(params, "amplitude")? {
+                SquareamplitudeInput.set(&mut square, amplitude);
+            }
+            Ok(square)
+        });
+
+        registry.register(
+            "rume::Pulse",
+            &["frequency", "amplitude", "width"],
+            |params| {
+                let mut pulse = Pulse::new();
+                if let Some(frequency) = number(params, "frequency")? {
+                    PulsefrequencyInput.set(&mut pulse, frequency);
+                }
+                if let Some(amplitude) = number(params, "amplitude")? {
+                    PulseamplitudeInput.set(&mut pulse, amplitude);
+                }
+                if let Some(width) = number(params, "width")? {
+                    PulsewidthInput.set(&mut pulse, width);
+                }
+                Ok(pulse)
+            },
+        );
+
+        registry.register("rume::Triangle", &["frequency", "amplitude"], |params| {
+            let mut triangle = Triangle::new();
+            if let Some(frequency) = number(params, "frequency")? {
+                TrianglefrequencyInput.set(&mut triangle, frequency);
+            }
+            if let Some(amplitude) = number(params, "amplitude")? {
+                TriangleamplitudeInput.set(&mut triangle, amplitude);
+            }
+            Ok(triangle)
+        });
+
+        registry.register("rume::Svf", &["cutoff", "q"], |params| {
+            let mut svf = Svf::new();
+            if let Some(cutoff) = number(params, "cutoff")? {
+                SvfcutoffInput.set(&mut svf, cutoff);
+            }
+            if let Some(q) = number(params, "q")? {
+                SvfqInput.set(&mut svf, q);
+            }
+            Ok(svf)
+        });
+
+        registry.register(
+            "rume::Adsr",
+            &["attack", "decay", "sustain", "release", "curve", "mode"],
+            |params| {
+                let mut adsr = Adsr::new();
+                if let Some(curve) = choice(params, "curve", ADSR_CURVES)? {
+                    adsr = adsr.with_curve(curve);
+                }
+                if let Some(mode) = choice(params, "mode", ADSR_MODES)? {
+                    adsr = adsr.with_mode(mode);
+                }
+                if let Some(attack) = number(params, "attack")? {
+                    AdsrattackInput.set(&mut adsr, attack);
+                }
+                if let Some(decay) = number(params, "decay")? {
+                    AdsrdecayInput.set(&mut adsr, decay);
+                }
+                if let Some(sustain) = number(params, "sustain")? {
+                    AdsrsustainInput.set(&mut adsr, sustain);
+                }
+                if let Some(release) = number(params, "release")? {
+                    AdsrreleaseInput.set(&mut adsr, release);
+                }
+                Ok(adsr)
+            },
+        );
+
+        registry.register(
+            "rume::Lfo",
+            &[
+                "rate", "tempo", "phase", "width", "shape", "polarity", "beats", "seed",
+            ],
+            |params| {
+                let shape = choice(params, "shape", LFO_SHAPES)?.unwrap_or_default();
+                let mut lfo = Lfo::new(shape);
+                if let Some(polarity) = choice(params, "polarity", LFO_POLARITIES)? {
+                    lfo = lfo.with_polarity(polarity);
+                }
+                match number(params, "beats")? {
+                    Some(beats) if beats > 0.0 => lfo = lfo.with_beats(beats),
+                    Some(_) => return Err("beats should be above zero".to_string()),
+                    None => {}
+                }
+                match number(params, "seed")? {
+                    Some(seed) if seed >= 0.0 && seed.fract() == 0.0 => {
+                        lfo = lfo.with_seed(seed as u32)
+                    }
+                    Some(_) => return Err("seed should be a whole number".to_string()),
+                    None => {}
+                }
+                if let Some(rate) = number(params, "rate")? {
+                    LforateInput.set(&mut lfo, rate);
+                }
+                if let Some(tempo) = number(params, "tempo")? {
+                    LfotempoInput.set(&mut lfo, tempo);
+                }
+                if let Some(phase) = number(params, "phase")? {
+                    LfophaseInput.set(&mut lfo, phase);
+                }
+                if let Some(width) = number(params, "width")? {
+                    LfowidthInput.set(&mut lfo, width);
+                }
+                Ok(lfo)
+            },
+        );
+
+        let biquads = [
+            ("rume::Lowpass", BiquadKind::Lowpass),
+            ("rume::Highpass", BiquadKind::Highpass),
+            ("rume::Bandpass", BiquadKind::Bandpass),
+            ("rume::Notch", BiquadKind::Notch),
+            ("rume::Allpass", BiquadKind::Allpass),
+            ("rume::Peaking", BiquadKind::Peaking),
+            ("rume::LowShelf", BiquadKind::LowShelf),
+            ("rume::HighShelf", BiquadKind::HighShelf),
+        ];
+        for (name, kind) in biquads.iter().copied() {
+            registry.register(name, &["frequency", "q", "gain"], move |params| {
+                let mut biquad = Biquad::new(kind);
+                if let Some(frequency) = number(params, "frequency")? {
+                    BiquadfrequencyInput.set(&mut biquad, frequency);
+                }
+                if let Some(q) = number(params, "q")? {
+                    BiquadqInput.set(&mut biquad, q);
+                }
+                if let Some(gain) = number(params, "gain")? {
+                    BiquadgainInput.set(&mut biquad, gain);
+                }
+                Ok(biquad)
+            });
+        }
+
+        registry
+    }
+}
+
+const ADSR_CURVES: &[(&str, AdsrCurve)] = &[
+    ("linear", AdsrCurve::Linear),
+    ("exponential", AdsrCurve::Exponential),
+];
+
+const ADSR_MODES: &[(&str, AdsrMode)] = &[
+    ("retrigger", AdsrMode::Retrigger),
+    ("legato", AdsrMode::Legato),
+];
+
+const LFO_SHAPES: &[(&str, LfoShape)] = &[
+    ("sine", LfoShape::Sine),
+    ("triangle", LfoShape::Triangle),
+    ("rising_saw", LfoShape::RisingSaw),
+    ("falling_saw", LfoShape::FallingSaw),
+    ("square", LfoShape::Square),
+    ("pulse", LfoShape::Pulse),
+    ("sample_and_hold", LfoShape::SampleAndHold),
+];
+
+const LFO_POLARITIES: &[(&str, LfoPolarity)] = &[
+    ("bipolar", LfoPolarity::Bipolar),
+    ("unipolar", LfoPolarity::Unipolar),
+];
+
+/// The number given for the parameter `key`, if any.
+fn number(params: &Params, key: &str) -> Result<Option<f32>, String> {
+    params
+        .get(key)
+        .map(|param| {
+            param
+                .as_number()
+                .ok_or_else(|| format!("{} should be a number", key))
+        })
+        .transpose()
+}
+
+/// The choice named by the parameter `key`,
+/// if any, out of the named `choices`.
+fn choice<T: Copy>(params: &Params, key: &str, choices: &[(&str, T)]) -> Result<Option<T>, String> {
+    let param = match params.get(key) {
+        Some(param) => param,
+        None => return Ok(None),
+    };
+
+    choices
+        .iter()
+        .find(|(name, _)| param.as_name() == Some(*name))
+        .map(|(_, choice)| Some(*choice))
+        .ok_or_else(|| {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            format!("{} should be one of {}", key, names.join(", "))
+        })
+}
+
+impl ProcessorRegistry {
+    /// A registry that knows no processors.
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Register a processor type under `name`, taking
+    /// the parameters named in `params`. Any other
+    /// parameter is rejected before the factory runs,
+    /// which may reject the rest with a reason.
+    pub fn register<P, F>(&mut self, name: &str, params: &[&str], factory: F)
+    where
+        P: IndexedPorts + Send + 'static,
+        F: Fn(&Params) -> Result<P, String> + 'static,
+    {
+        let known: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        let factory: Factory = Box::new(move |builder, params| {
+            if let Some(unknown) = params.keys().find(|key| !known.contains(key)) {
+                return Err(format!("{} is not one of its parameters", unknown));
+            }
+            let processor = builder.add(factory(params)?);
+            Ok(PatchNode::of(processor))
+        });
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    /// Build the graph a description describes,
+    /// along with the streams of its endpoints.
+    pub fn build(
+        &self,
+        description: &GraphDescription,
+    ) -> Result<(SignalChain, PatchEndpoints), PatchError> {
+        let mut builder = SignalChainBuilder::default();
+        let mut nodes = HashMap::<&str, PatchNode>::new();
+        let mut endpoints = PatchEndpoints::default();
+
+        for input in &description.inputs {
+            unique(&nodes, &input.name)?;
+            let (producer, consumer) = make_input_endpoint();
+            let endpoint = builder.add(input.to_endpoint(consumer));
+            nodes.insert(&input.name, PatchNode::of(endpoint));
+            endpoints.inputs.insert(input.name.clone(), producer);
+        }
+
+        for output in &description.outputs {
+            unique(&nodes, output)?;
+            let (producer, consumer) = make_output_endpoint();
+            let endpoint = builder.add(OutputEndpoint::new(producer));
+            nodes.insert(output, PatchNode::of(endpoint));
+            endpoints.outputs.insert(output.clone(), consumer);
+        }
+
+        for processor in &description.processors {
+            unique(&nodes, &processor.name)?;
+            let factory = self
+                .factories
+                .get(&processor.kind)
+                .ok_or_else(|| PatchError::UnknownType(processor.kind.clone()))?;
+            let node = factory(&mut builder, &processor.params).map_err(|reason| {
+                PatchError::InvalidParams {
+                    processor: processor.name.clone(),
+                    reason,
+                }
+            })?;
+            nodes.insert(&processor.name, node);
+        }
+
+        for connection in &description.connections {
+            let node = |name: &String| {
+                nodes
+                    .get(name.as_str())
+                    .ok_or_else(|| PatchError::UnknownName(name.clone()))
+            };
+
+            let output = (node(&connection.from)?.output)(connection.output).ok_or_else(|| {
+                PatchError::UnknownOutput {
+                    processor: connection.from.clone(),
+                    port: connection.output,
+                }
+            })?;
+            let input = (node(&connection.to)?.input)(connection.input).ok_or_else(|| {
+                PatchError::UnknownInput {
+                    processor: connection.to.clone(),
+                    port: connection.input,
+                }
+            })?;
+
+            builder = match connection.feedback {
+                true => builder.try_feedback(output, input)?,
+                false => builder.try_connection(output, input)?,
+            };
+        }
+
+        Ok((builder.try_build()?, endpoints))
+    }
+}
+
+fn unique(nodes: &HashMap<&str, PatchNode>, name: &str) -> Result<(), PatchError> {
+    match nodes.contains_key(name) {
+        true => Err(PatchError::DuplicateName(name.to_string())),
+        false => Ok(()),
+    }
+}
+
+impl InputDescription {
+    fn to_endpoint(&self, stream: InputStreamConsumer) -> InputEndpoint {
+        let mut builder = InputEndpointBuilder::new(stream);
+
+        if let Some(kind) = self.kind {
+            builder = builder.kind(kind.into());
+        }
+        if let Some([start, end]) = self.range {
+            builder = builder.range(start..end);
+        }
+        if let Some(init) = self.init {
+            builder = builder.init(init);
+        }
+        if let Some(smooth) = self.smooth {
+            builder = builder.smooth_millis(smooth);
+        }
+
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn describe(processors: &[(&str, &str)], connections: &[(&str, usize, &str, usize)]) -> String {
+        let processors: Vec<_> = processors
+            .iter()
+            .map(|(name, kind)| format!(r#"{{ "name": "{}", "type": "{}" }}"#, name, kind))
+            .collect();
+        let connections: Vec<_> = connections
+            .iter()
+            .map(|(from, output, to, input)| {
+                format!(
+                    r#"{{ "from": "{}", "output": {}, "to": "{}", "input": {} }}"#,
+                    from, output, to, input
+                )
+            })
+            .collect();
+
+        format!(
+            r#"{{ "processors": [{}], "connections": [{}] }}"#,
+            processors.join(","),
+            connections.join(",")
+        )
+    }
+
+    fn build(json: &str) -> Result<SignalChain, PatchError> {
+        let description = GraphDescription::from_json(json)?;
+        let (chain, _) = ProcessorRegistry::default().build(&description)?;
+        Ok(chain)
+    }
+
+    #[test]
+    fn descriptions_survive_a_round_trip() {
+        let json = r#"{
+            "inputs": [{ "name": "gate", "kind": "trigger", "range": [0.0, 1.0] }],
+            "outputs": ["out"],
+            "processors": [{ "name": "saw", "type": "rume::Saw", "params": { "frequency": 110.0 } }],
+            "connections": [
+                { "from": "gate", "to": "saw", "input": 1 },
+                { "from": "saw", "to": "out" }
+            ]
+        }"#;
+
+        let description = GraphDescription::from_json(json).unwrap();
+        assert_eq!(description.inputs[0].kind, Some(InputKind::Trigger));
+        assert_eq!(description.connections[1].output, 0);
+        assert_eq!(
+            description.processors[0].params["frequency"],
+            Param::Number(110.0)
+        );
+
+        let reloaded = GraphDescription::from_json(&description.to_json()).unwrap();
+        assert_eq!(reloaded, description);
+    }
+
+    #[test]
+    fn pulse_kinds_carry_their_length() {
+        let json = r#"{
+            "inputs": [
+                { "name": "hit", "kind": { "pulse": 32 } },
+                { "name": "click", "kind": { "pulse_ms": 2.5 } },
+                { "name": "hold", "kind": "gate" },
+                { "name": "step", "kind": "sample_and_hold" }
+            ]
+        }"#;
+
+        let description = GraphDescription::from_json(json).unwrap();
+        let kinds: Vec<_> = description.inputs.iter().map(|input| input.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                Some(InputKind::Pulse(32)),
+                Some(InputKind::PulseMillis(2.5)),
+                Some(InputKind::Gate),
+                Some(InputKind::SampleAndHold)
+            ]
+        );
+    }
+
+    #[test]
+    fn processors_are_connected_by_name_and_port() {
+        let json = r#"{
+            "outputs": ["out"],
+            "processors": [
+                { "name": "lvl", "type": "rume::Value", "params": { "value": 0.5 } },
+                { "name": "sine", "type": "rume::Sine", "params": { "frequency": 12000.0 } }
+            ],
+            "connections": [
+                { "from": "lvl", "to": "sine", "input": 1 },
+                { "from": "sine", "to": "out" }
+            ]
+        }"#;
+        let description = GraphDescription::from_json(json).unwrap();
+        let (mut chain, mut endpoints) = ProcessorRegistry::default().build(&description).unwrap();
+
+        chain.prepare(48_000.into());
+        chain.render(4);
+
+        let out = endpoints.outputs.get_mut("out").unwrap();
+        let peak = (0..4)
+            .map(|_| out.dequeue().unwrap().abs())
+            .fold(0.0, f32::max);
+        assert!((peak - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn unknown_params_are_rejected() {
+        let json = r#"{
+            "processors": [{ "name": "sine", "type": "rume::Sine", "params": { "frequncy": 440.0 } }]
+        }"#;
+        assert_eq!(
+            build(json).err(),
+            Some(PatchError::InvalidParams {
+                processor: "sine".to_string(),
+                reason: "frequncy is not one of its parameters".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn choices_are_given_by_name() {
+        let adsr = |params: &str| {
+            format!(
+                r#"{{ "processors": [{{ "name": "env", "type": "rume::Adsr", "params": {} }}] }}"#,
+                params
+            )
+        };
+        let reason = |json: &str| match build(json).err() {
+            Some(PatchError::InvalidParams { reason, .. }) => Some(reason),
+            _ => None,
+        };
+
+        assert!(build(&adsr(r#"{ "curve": "exponential", "mode": "legato" }"#)).is_ok());
+        assert_eq!(
+            reason(&adsr(r#"{ "curve": "cubic" }"#)).as_deref(),
+            Some("curve should be one of linear, exponential")
+        );
+        assert_eq!(
+            reason(&adsr(r#"{ "mode": 1 }"#)).as_deref(),
+            Some("mode should be one of retrigger, legato")
+        );
+        assert_eq!(
+            reason(&adsr(r#"{ "attack": "fast" }"#)).as_deref(),
+            Some("attack should be a number")
+        );
+    }
+
+    #[test]
+    fn lfos_take_their_shape_polarity_and_beats() {
+        let lfo = |params: &str| {
+            format!(
+                r#"{{
+                    "outputs": ["out"],
+                    "processors": [{{ "name": "lfo", "type": "rume::Lfo", "params": {} }}],
+                    "connections": [{{ "from": "lfo", "to": "out" }}]
+                }}"#,
+                params
+            )
+        };
+        let reason = |json: &str| match build(json).err() {
+            Some(PatchError::InvalidParams { reason, .. }) => Some(reason),
+            _ => None,
+        };
+
+        let render = |params: &str| {
+            let description = GraphDescription::from_json(&lfo(params)).unwrap();
+            let (mut chain, mut endpoints) =
+                ProcessorRegistry::default().build(&description).unwrap();
+            chain.prepare(1000.into());
+            chain.render(4);
+            let mut rendered = [0.0; 4];
+            let out = endpoints.outputs.get_mut("out").unwrap();
+            out.read_into(&mut rendered);
+            rendered
+        };
+
+        let saw = render(r#"{ "shape": "rising_saw", "polarity": "unipolar", "rate": 250 }"#);
+        assert_eq!(saw, [0.0, 0.25, 0.5, 0.75]);
+
+        let synced = render(
+            r#"{ "shape": "rising_saw", "polarity": "unipolar", "beats": 1, "tempo": 15000 }"#,
+        );
+        assert_eq!(synced, [0.0, 0.25, 0.5, 0.75]);
+
+        let pulse = render(r#"{ "shape": "pulse", "rate": 250, "width": 0.25 }"#);
+        assert_eq!(pulse, [1.0, -1.0, -1.0, -1.0]);
+
+        let held = |seed: u32| {
+            render(&format!(
+                r#"{{ "shape": "sample_and_hold", "rate": 250, "seed": {} }}"#,
+                seed
+            ))
+        };
+        assert_eq!(held(7), held(7));
+        assert_ne!(held(7)[0], held(8)[0]);
+
+        assert!(build(&lfo(r#"{ "beats": 4 }"#)).is_ok());
+        assert_eq!(
+            reason(&lfo(r#"{ "shape": "noise" }"#)).as_deref(),
+            Some(
+                "shape should be one of sine, triangle, rising_saw, falling_saw, \
+                 square, pulse, sample_and_hold"
+            )
+        );
+        assert_eq!(
+            reason(&lfo(r#"{ "polarity": "positive" }"#)).as_deref(),
+            Some("polarity should be one of bipolar, unipolar")
+        );
+        assert_eq!(
+            reason(&lfo(r#"{ "beats": 0 }"#)).as_deref(),
+            Some("beats should be above zero")
+        );
+        assert_eq!(
+            reason(&lfo(r#"{ "seed": 1.5 }"#)).as_deref(),
+            Some("seed should be a whole number")
+        );
+    }
+
+    #[test]
+    fn unknown_types_names_and_ports_fail() {
+        let json = describe(&[("osc", "rume::Wavetable")], &[]);
+        assert_eq!(
+            build(&json).err(),
+            Some(PatchError::UnknownType("rume::Wavetable".to_string()))
+        );
+
+        let json = describe(&[("sine", "rume::Sine")], &[("lvl", 0, "sine", 0)]);
+        assert_eq!(
+            build(&json).err(),
+            Some(PatchError::UnknownName("lvl".to_string()))
+        );
+
+        let json = describe(
+            &[("lvl", "rume::Value"), ("sine", "rume::Sine")],
+            &[("lvl", 0, "sine", 2)],
+        );
+        assert_eq!(
+            build(&json).err(),
+            Some(PatchError::UnknownInput {
+                processor: "sine".to_string(),
+                port: 2
+            })
+        );
+
+        let json = describe(&[("sine", "rume::Sine"), ("sine", "rume::Saw")], &[]);
+        assert_eq!(
+            build(&json).err(),
+            Some(PatchError::DuplicateName("sine".to_string()))
+        );
+    }
+
+    #[test]
+    fn user_processors_can_be_registered() {
+        let mut registry = ProcessorRegistry::empty();
+        registry.register("gain", &["gain"], |params| {
+            match params.get("gain").and_then(Param::as_number) {
+                Some(gain) if gain >= 0.0 => Ok(Value::new(gain)),
+                _ => Err("gain must be positive".to_string()),
+            }
+        });
+
+        let description = GraphDescription::from_json(
+            r#"{ "processors": [{ "name": "g", "type": "gain", "params": { "gain": 2.0 } }] }"#,
+        )
+        .unwrap();
+        assert!(registry.build(&description).is_ok());
+
+        let description = GraphDescription::from_json(
+            r#"{ "processors": [{ "name": "g", "type": "gain", "params": { "gain": -2.0 } }] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            registry.build(&description).err(),
+            Some(PatchError::InvalidParams {
+                processor: "g".to_string(),
+                reason: "gain must be positive".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn graph_errors_are_passed_on() {
+        let json = describe(
+            &[("a", "rume::Sine"), ("b", "rume::Sine")],
+            &[("a", 0, "b", 0), ("b", 0, "a", 0)],
+        );
+        assert!(matches!(
+            build(&json).err(),
+            Some(PatchError::Graph(GraphError::Cycle(_)))
+        ));
+    }
+
+    #[test]
+    fn each_endpoint_has_its_own_stream() {
+        let json = r#"{
+            "inputs": [{ "name": "freq" }, { "name": "amp" }],
+            "outputs": ["freq_out", "amp_out"],
+            "connections": [
+                { "from": "freq", "to": "freq_out" },
+                { "from": "amp", "to": "amp_out" }
+            ]
+        }"#;
+        let description = GraphDescription::from_json(json).unwrap();
+        let (mut chain, mut endpoints) = ProcessorRegistry::default().build(&description).unwrap();
+
+        endpoints
+            .inputs
+            .get_mut("freq")
+            .unwrap()
+            .enqueue(440.0)
+            .unwrap();
+        endpoints
+            .inputs
+            .get_mut("amp")
+            .unwrap()
+            .enqueue(0.5)
+            .unwrap();
+        chain.prepare(48_000.into());
+        chain.render(1);
+
+        let mut read = |name: &str| endpoints.outputs.get_mut(name).unwrap().dequeue();
+        assert_eq!(read("freq_out"), Some(440.0));
+        assert_eq!(read("amp_out"), Some(0.5));
+        assert_eq!(read("freq_out"), None);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{convert::tick, *};
+use core::f32::consts::LN_2;
+
+/// The shape of the segments of an `Adsr`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AdsrCurve {
+    /// Straight lines.
+    #[default]
+    Linear,
+    /// Exponential approaches, as analog
+    /// envelopes make: attacks slow down
+    /// towards the peak, decays and releases
+    /// towards the level they fall to.
+    Exponential,
+}
+
+/// What an `Adsr` does when its gate opens
+/// while it is still sounding.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AdsrMode {
+    /// Attack again from the current level.
+    #[default]
+    Retrigger,
+    /// Carry on, returning to the sustain
+    /// level if it was releasing.
+    Legato,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Stage {
+    #[default]
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// How far past the end of a segment its
+/// exponential approach aims, relative to
+/// the length of the segment, so that it
+/// gets there in time.
+const ATTACK_OVERSHOOT: f32 = 0.3;
+const DECAY_OVERSHOOT: f32 = 0.0001;
+
+/// An envelope generator. It attacks when its
+/// `gate` input rises above zero, decays to the
+/// `sustain` level, which it holds while the gate
+/// is open, and releases once it closes. The
+/// `attack`, `decay` and `release` times are in
+/// milliseconds. Its outputs are the `envelope`
+/// and whether it is `active`, 1.0 until it has
+/// fully released, for a voice allocator to read.
+#[processor]
+pub struct Adsr {
+    #[input]
+    gate: f32,
+
+    #[input]
+    attack: f32,
+
+    #[input]
+    decay: f32,
+
+    #[input]
+    sustain: f32,
+
+    #[input]
+    release: f32,
+
+    #[output]
+    envelope: f32,
+
+    #[output]
+    active: f32,
+
+    curve: AdsrCurve,
+    mode: AdsrMode,
+    sample_rate: f32,
+    stage: Stage,
+    open: bool,
+    released_from: f32,
+}
+
+impl Adsr {
+    pub fn new() -> Self {
+        Self {
+            attack: 10.0,
+            decay: 100.0,
+            sustain: 0.7,
+            release: 200.0,
+            sample_rate: 48_000.0,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_curve(mut self, curve: AdsrCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: AdsrMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn samples(&self, ms: f32) -> f32 {
+        tick::from_millis(ms, self.sample_rate).max(1.0)
+    }
+
+    fn open(&mut self) {
+        self.stage = match (self.mode, self.stage) {
+            (AdsrMode::Legato, Stage::Release) => Stage::Decay,
+            (AdsrMode::Legato, Stage::Idle) | (AdsrMode::Retrigger, _) => Stage::Attack,
+            (AdsrMode::Legato, stage) => stage,
+        };
+    }
+
+    fn close(&mut self) {
+        if self.stage != Stage::Idle {
+            self.stage = Stage::Release;
+            self.released_from = self.envelope;
+        }
+    }
+
+    /// Move `envelope` a sample along a segment of
+    /// `ms` from `from` towards `to`, returning
+    /// whether it got there.
+    fn step(&mut self, from: f32, to: f32, ms: f32, overshoot: f32) -> bool {
+        let samples = self.samples(ms);
+        let rising = to > from;
+
+        self.envelope = match self.curve {
+            AdsrCurve::Linear => self.envelope + (to - from) / samples,
+            AdsrCurve::Exponential => {
+                let ratio = overshoot / (1.0 + overshoot);
+                let coefficient = (ratio.log2() * LN_2 / samples).exp();
+                let overshoot = overshoot * (to - from);
+                let target = to + overshoot;
+                target + (self.envelope - target) * coefficient
+            }
+        };
+
+        let margin = 1e-5 * (to - from).abs();
+        let arrived = match rising {
+            true => self.envelope >= to - margin,
+            false => self.envelope <= to + margin,
+        };
+        if arrived {
+            self.envelope = to;
+        }
+        arrived
+    }
+}
+
+impl Processor for Adsr {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_rate = config.sample_rate as f32;
+        self.stage = Stage::Idle;
+        self.open = false;
+        self.envelope = 0.0;
+        self.active = 0.0;
+    }
+
+    fn process(&mut self) {
+        let open = self.gate > 0.0;
+        match (self.open, open) {
+            (false, true) => self.open(),
+            (true, false) => self.close(),
+            _ => {}
+        }
+        self.open = open;
+
+        let sustain = self.sustain.clamp(0.0, 1.0);
+        match self.stage {
+            Stage::Idle => self.envelope = 0.0,
+            Stage::Attack => {
+                if self.step(0.0, 1.0, self.attack, ATTACK_OVERSHOOT) {
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                let from = if self.envelope > sustain { 1.0 } else { 0.0 };
+                if self.envelope == sustain || self.step(from, sustain, self.decay, DECAY_OVERSHOOT)
+                {
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => self.envelope = sustain,
+            Stage::Release => {
+                let from = self.released_from;
+                if self.envelope <= 0.0 || self.step(from, 0.0, self.release, DECAY_OVERSHOOT) {
+                    self.envelope = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+
+        self.active = match self.stage {
+            Stage::Idle => 0.0,
+            _ => 1.0,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An envelope whose times in milliseconds
+    /// are numbers of samples.
+    fn adsr(curve: AdsrCurve, mode: AdsrMode) -> Adsr {
+        let mut adsr = Adsr::new().with_curve(curve).with_mode(mode);
+        AdsrattackInput.set(&mut adsr, 4.0);
+        AdsrdecayInput.set(&mut adsr, 2.0);
+        AdsrsustainInput.set(&mut adsr, 0.5);
+        AdsrreleaseInput.set(&mut adsr, 4.0);
+        adsr.prepare(1000.into());
+        adsr
+    }
+
+    fn run(adsr: &mut Adsr, gate: f32, samples: usize) -> Vec<f32> {
+        AdsrgateInput.set(adsr, gate);
+        (0..samples)
+            .map(|_| {
+                adsr.process();
+                AdsrenvelopeOutput.get(adsr)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn linear_segments_take_their_time() {
+        let mut adsr = adsr(AdsrCurve::Linear, AdsrMode::Retrigger);
+
+        assert_eq!(run(&mut adsr, 0.0, 2), [0.0, 0.0]);
+        assert_eq!(AdsractiveOutput.get(&mut adsr), 0.0);
+
+        assert_eq!(run(&mut adsr, 1.0, 4), [0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(run(&mut adsr, 1.0, 4), [0.75, 0.5, 0.5, 0.5]);
+        assert_eq!(AdsractiveOutput.get(&mut adsr), 1.0);
+
+        assert_eq!(run(&mut adsr, 0.0, 5), [0.375, 0.25, 0.125, 0.0, 0.0]);
+        assert_eq!(AdsractiveOutput.get(&mut adsr), 0.0);
+    }
+
+    #[test]
+    fn exponential_segments_curve_and_take_their_time() {
+        let mut adsr = adsr(AdsrCurve::Exponential, AdsrMode::Retrigger);
+
+        let attack = run(&mut adsr, 1.0, 4);
+        assert!(attack[0] > 0.25);
+        assert!(attack.windows(2).all(|pair| pair[1] - pair[0] < attack[0]));
+        assert_eq!(attack[3], 1.0);
+
+        let decay = run(&mut adsr, 1.0, 2);
+        assert!(decay[0] < 0.75);
+        assert_eq!(decay[1], 0.5);
+
+        let release = run(&mut adsr, 0.0, 5);
+        assert!(release[0] < 0.375);
+        assert_eq!(&release[3..], [0.0, 0.0]);
+        assert_eq!(AdsractiveOutput.get(&mut adsr), 0.0);
+    }
+
+    #[test]
+    fn retrigger_attacks_again_and_legato_carries_on() {
+        let mut retrigger = adsr(AdsrCurve::Linear, AdsrMode::Retrigger);
+        run(&mut retrigger, 1.0, 8);
+        run(&mut retrigger, 0.0, 2);
+        assert_eq!(run(&mut retrigger, 1.0, 3), [0.5, 0.75, 1.0]);
+
+        let mut legato = adsr(AdsrCurve::Linear, AdsrMode::Legato);
+        run(&mut legato, 1.0, 8);
+        run(&mut legato, 0.0, 2);
+        assert_eq!(run(&mut legato, 1.0, 3), [0.5, 0.5, 0.5]);
+
+        let mut legato = adsr(AdsrCurve::Linear, AdsrMode::Legato);
+        run(&mut legato, 1.0, 2);
+        run(&mut legato, 0.0, 1);
+        run(&mut legato, 1.0, 1);
+        assert_eq!(run(&mut legato, 1.0, 2), [0.5, 0.5]);
+    }
+}
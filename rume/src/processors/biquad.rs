@@ -0,0 +1,256 @@
+use crate::*;
+use core::f32::consts::PI;
+
+/// The response of a `Biquad`, as given
+/// by the RBJ Audio EQ Cookbook.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BiquadKind {
+    #[default]
+    Lowpass,
+    Highpass,
+    /// Bandpass with a 0 dB peak.
+    Bandpass,
+    Notch,
+    Allpass,
+    /// Boosts or cuts by `gain` around `frequency`.
+    Peaking,
+    /// Boosts or cuts by `gain` below `frequency`.
+    LowShelf,
+    /// Boosts or cuts by `gain` above `frequency`.
+    HighShelf,
+}
+
+/// A second order filter. Its inputs are the
+/// signal, then the `frequency` in hertz, the
+/// `q` and the `gain` in decibels of shelves and
+/// peaks, which can all change at audio rate.
+/// The coefficients are worked out again at
+/// `prepare` and whenever one of them changes.
+#[processor]
+pub struct Biquad {
+    #[sample]
+    sample: f32,
+
+    #[input]
+    frequency: f32,
+
+    #[input]
+    q: f32,
+
+    #[input]
+    gain: f32,
+
+    kind: BiquadKind,
+    sample_rate: f32,
+    settings: [f32; 3],
+    b: [f32; 3],
+    a: [f32; 2],
+    state: [f32; 2],
+}
+
+impl Biquad {
+    pub fn new(kind: BiquadKind) -> Self {
+        Self {
+            kind,
+            frequency: 1000.0,
+            q: core::f32::consts::FRAC_1_SQRT_2,
+            sample_rate: 48_000.0,
+            ..Self::default()
+        }
+    }
+
+    pub fn kind(&self) -> BiquadKind {
+        self.kind
+    }
+
+    fn update(&mut self) {
+        let settings = [self.frequency, self.q, self.gain];
+        if settings == self.settings {
+            return;
+        }
+        self.settings = settings;
+
+        let nyquist = self.sample_rate * 0.5;
+        let frequency = self.frequency.max(1.0).min(nyquist * 0.99);
+        let w0 = 2.0 * PI * frequency / self.sample_rate;
+        let (sin, cos) = (w0.sin(), w0.cos());
+        let alpha = sin / (2.0 * self.q.max(1e-3));
+        let a = 10_f32.powf(self.gain / 40.0);
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b, a) = match self.kind {
+            BiquadKind::Lowpass => (
+                [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadKind::Highpass => (
+                [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadKind::Bandpass => ([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha]),
+            BiquadKind::Notch => (
+                [1.0, -2.0 * cos, 1.0],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadKind::Allpass => (
+                [1.0 - alpha, -2.0 * cos, 1.0 + alpha],
+                [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            ),
+            BiquadKind::Peaking => (
+                [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+                [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+            ),
+            BiquadKind::LowShelf => (
+                [
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                ],
+                [
+                    (a + 1.0) + (a - 1.0) * cos + shelf,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - shelf,
+                ],
+            ),
+            BiquadKind::HighShelf => (
+                [
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                ],
+                [
+                    (a + 1.0) - (a - 1.0) * cos + shelf,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - shelf,
+                ],
+            ),
+        };
+
+        self.b = [b[0] / a[0], b[1] / a[0], b[2] / a[0]];
+        self.a = [a[1] / a[0], a[2] / a[0]];
+    }
+}
+
+impl Processor for Biquad {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_rate = config.sample_rate as f32;
+        self.settings = [f32::NAN; 3];
+        self.state = [0.0; 2];
+        self.update();
+    }
+
+    fn process(&mut self) {
+        self.update();
+
+        let input = self.sample;
+        let output = self.b[0] * input + self.state[0];
+        self.state[0] = self.b[1] * input - self.a[0] * output + self.state[1];
+        self.state[1] = self.b[2] * input - self.a[1] * output;
+        self.sample = output;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// The gain of `filter` at `frequency`,
+    /// from the peak of a settled sine.
+    fn gain_at(filter: &mut Biquad, frequency: f32) -> f32 {
+        filter.prepare(SAMPLE_RATE.into());
+
+        let mut peak = 0.0_f32;
+        for n in 0..SAMPLE_RATE / 4 {
+            let phase = 2.0 * PI * frequency * n as f32 / SAMPLE_RATE as f32;
+            BiquadsampleInput.set(filter, phase.sin());
+            filter.process();
+            if n > SAMPLE_RATE / 8 {
+                peak = peak.max(BiquadsampleOutput.get(filter).abs());
+            }
+        }
+        peak
+    }
+
+    fn filter(kind: BiquadKind, frequency: f32, q: f32, gain: f32) -> Biquad {
+        let mut filter = Biquad::new(kind);
+        BiquadfrequencyInput.set(&mut filter, frequency);
+        BiquadqInput.set(&mut filter, q);
+        BiquadgainInput.set(&mut filter, gain);
+        filter
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn passes_keep_their_band_and_cut_the_rest() {
+        let mut lowpass = filter(BiquadKind::Lowpass, 1000.0, 0.707, 0.0);
+        assert_near(gain_at(&mut lowpass, 100.0), 1.0, 0.01);
+        assert!(gain_at(&mut lowpass, 10_000.0) < 0.02);
+
+        let mut highpass = filter(BiquadKind::Highpass, 1000.0, 0.707, 0.0);
+        assert!(gain_at(&mut highpass, 100.0) < 0.02);
+        assert_near(gain_at(&mut highpass, 10_000.0), 1.0, 0.01);
+
+        let mut bandpass = filter(BiquadKind::Bandpass, 1000.0, 2.0, 0.0);
+        assert_near(gain_at(&mut bandpass, 1000.0), 1.0, 0.01);
+        assert!(gain_at(&mut bandpass, 100.0) < 0.1);
+        assert!(gain_at(&mut bandpass, 10_000.0) < 0.1);
+    }
+
+    #[test]
+    fn notch_removes_its_frequency_and_allpass_keeps_all() {
+        let mut notch = filter(BiquadKind::Notch, 1000.0, 0.707, 0.0);
+        assert!(gain_at(&mut notch, 1000.0) < 0.01);
+        assert_near(gain_at(&mut notch, 100.0), 1.0, 0.02);
+
+        for frequency in [100.0, 1000.0, 10_000.0].iter() {
+            let mut allpass = filter(BiquadKind::Allpass, 1000.0, 0.707, 0.0);
+            assert_near(gain_at(&mut allpass, *frequency), 1.0, 0.01);
+        }
+    }
+
+    #[test]
+    fn peaks_and_shelves_apply_their_gain() {
+        let boost = 10_f32.powf(6.0 / 20.0);
+
+        let mut peaking = filter(BiquadKind::Peaking, 1000.0, 1.0, 6.0);
+        assert_near(gain_at(&mut peaking, 1000.0), boost, 0.01);
+        assert_near(gain_at(&mut peaking, 20.0), 1.0, 0.01);
+
+        let mut low_shelf = filter(BiquadKind::LowShelf, 1000.0, 0.707, 6.0);
+        assert_near(gain_at(&mut low_shelf, 20.0), boost, 0.01);
+        assert_near(gain_at(&mut low_shelf, 15_000.0), 1.0, 0.01);
+
+        let mut high_shelf = filter(BiquadKind::HighShelf, 1000.0, 0.707, -6.0);
+        assert_near(gain_at(&mut high_shelf, 20.0), 1.0, 0.01);
+        assert_near(gain_at(&mut high_shelf, 15_000.0), 1.0 / boost, 0.01);
+    }
+
+    #[test]
+    fn coefficients_follow_the_inputs() {
+        let mut lowpass = filter(BiquadKind::Lowpass, 1000.0, 0.707, 0.0);
+        lowpass.prepare(SAMPLE_RATE.into());
+        let coefficients = (lowpass.b, lowpass.a);
+
+        lowpass.process();
+        assert_eq!((lowpass.b, lowpass.a), coefficients);
+
+        BiquadfrequencyInput.set(&mut lowpass, 2000.0);
+        lowpass.process();
+        assert_ne!((lowpass.b, lowpass.a), coefficients);
+
+        let mut expected = filter(BiquadKind::Lowpass, 2000.0, 0.707, 0.0);
+        expected.prepare(SAMPLE_RATE.into());
+        assert_eq!((lowpass.b, lowpass.a), (expected.b, expected.a));
+    }
+}
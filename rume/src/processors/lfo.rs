@@ -0,0 +1,341 @@
+use crate::{convert::tick, *};
+use core::f32::consts::PI;
+
+/// The waveform of an `Lfo`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    RisingSaw,
+    FallingSaw,
+    Square,
+    /// A square whose duty cycle follows
+    /// the `width` input.
+    Pulse,
+    /// A random value, held for a cycle.
+    SampleAndHold,
+}
+
+/// The seed of the random values of an `Lfo`
+/// not given one, as xorshift needs a bit set.
+const SEED: u32 = 0x9e37_79b9;
+
+/// The range of the output of an `Lfo`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LfoPolarity {
+    /// From -1.0 to 1.0.
+    #[default]
+    Bipolar,
+    /// From 0.0 to 1.0.
+    Unipolar,
+}
+
+/// A low frequency oscillator. It runs at `rate`
+/// hertz, or once every so many beats of `tempo`
+/// when synced, starting its cycles `phase` turns
+/// in. A rising `reset` restarts the cycle, so it
+/// can follow a note or another clock.
+#[processor]
+pub struct Lfo {
+    #[input]
+    rate: f32,
+
+    #[input]
+    tempo: f32,
+
+    #[input]
+    phase: f32,
+
+    #[input]
+    width: f32,
+
+    #[input]
+    reset: f32,
+
+    #[output]
+    sample: f32,
+
+    shape: LfoShape,
+    polarity: LfoPolarity,
+    beats: Option<f32>,
+    sample_rate: f32,
+    phasor: Phasor,
+    offset: f32,
+    triggered: bool,
+    held: f32,
+    seed: u32,
+}
+
+impl Lfo {
+    pub fn new(shape: LfoShape) -> Self {
+        Self {
+            rate: 1.0,
+            tempo: 120.0,
+            width: 0.5,
+            shape,
+            sample_rate: 48_000.0,
+            phasor: Phasor::with_max(1.0),
+            seed: SEED,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_polarity(mut self, polarity: LfoPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Sync to the `tempo` input, running
+    /// one cycle every `beats` beats.
+    pub fn with_beats(mut self, beats: f32) -> Self {
+        self.beats = Some(beats);
+        self
+    }
+
+    /// Seed the random values of
+    /// `LfoShape::SampleAndHold`.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed.max(1);
+        self
+    }
+
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    fn cycles_per_sample(&self) -> f32 {
+        match self.beats {
+            Some(beats) if self.tempo > 0.0 && beats > 0.0 => {
+                1.0 / (tick::from_bpm(self.tempo, self.sample_rate) * beats)
+            }
+            Some(_) => 0.0,
+            None => convert::pitch::to_cycles(self.rate.max(0.0), self.sample_rate),
+        }
+    }
+
+    /// A xorshift random value in -1.0..1.0.
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+
+    /// Shift the phasor by the change of
+    /// the `phase` input since it last did,
+    /// drawing a new held value if the
+    /// shift moves it across a cycle start.
+    fn follow_offset(&mut self) {
+        let change = self.phase - self.offset;
+        if change == 0.0 {
+            return;
+        }
+        self.offset = self.phase;
+
+        let position = self.phasor.get() + change;
+        let mut shift = change % 1.0;
+        if shift < 0.0 {
+            shift += 1.0;
+        }
+        self.phasor.shift(shift);
+        if !(0.0..1.0).contains(&position) {
+            self.held = self.random();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.phasor.reset();
+        self.offset = 0.0;
+        self.follow_offset();
+        self.held = self.random();
+    }
+}
+
+impl Processor for Lfo {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_rate = config.sample_rate as f32;
+        self.phasor = Phasor::with_max(1.0);
+        if self.seed == 0 {
+            self.seed = SEED;
+        }
+        self.triggered = false;
+        self.restart();
+    }
+
+    fn process(&mut self) {
+        let triggered = self.reset > 0.0;
+        if triggered && !self.triggered {
+            self.restart();
+        }
+        self.triggered = triggered;
+        self.follow_offset();
+
+        let phase = self.phasor.get();
+        let value = match self.shape {
+            LfoShape::Sine => (phase * 2.0 * PI).sin(),
+            LfoShape::Triangle => waves::triangle(phase),
+            LfoShape::RisingSaw => waves::saw::rise(phase),
+            LfoShape::FallingSaw => waves::saw::fall(phase),
+            LfoShape::Square => waves::square(phase),
+            LfoShape::Pulse => waves::pwm(phase, self.width.clamp(0.0, 1.0)),
+            LfoShape::SampleAndHold => self.held,
+        };
+        self.sample = match self.polarity {
+            LfoPolarity::Bipolar => value,
+            LfoPolarity::Unipolar => (value + 1.0) * 0.5,
+        };
+
+        self.phasor.inc(self.cycles_per_sample());
+        if self.phasor.advance() < phase {
+            self.held = self.random();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn lfo(shape: LfoShape) -> Lfo {
+        let mut lfo = Lfo::new(shape);
+        LforateInput.set(&mut lfo, 250.0);
+        lfo
+    }
+
+    fn run(lfo: &mut Lfo, samples: usize) -> Vec<f32> {
+        (0..samples)
+            .map(|_| {
+                lfo.process();
+                LfosampleOutput.get(lfo)
+            })
+            .collect()
+    }
+
+    fn assert_near(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn shapes_follow_the_phase() {
+        let shapes = [
+            (LfoShape::Sine, [0.0, 1.0, 0.0, -1.0]),
+            (LfoShape::Triangle, [-1.0, 0.0, 1.0, 0.0]),
+            (LfoShape::RisingSaw, [-1.0, -0.5, 0.0, 0.5]),
+            (LfoShape::FallingSaw, [1.0, 0.5, 0.0, -0.5]),
+            (LfoShape::Square, [1.0, 1.0, -1.0, -1.0]),
+        ];
+        for (shape, expected) in shapes.iter() {
+            let mut lfo = lfo(*shape);
+            lfo.prepare(SAMPLE_RATE.into());
+            assert_near(&run(&mut lfo, 8), &[&expected[..], &expected[..]].concat());
+        }
+
+        let mut pulse = lfo(LfoShape::Pulse);
+        LfowidthInput.set(&mut pulse, 0.25);
+        pulse.prepare(SAMPLE_RATE.into());
+        assert_eq!(run(&mut pulse, 4), [1.0, -1.0, -1.0, -1.0]);
+    }
+
+    #[test]
+    fn unipolar_output_is_between_zero_and_one() {
+        let mut lfo = lfo(LfoShape::RisingSaw).with_polarity(LfoPolarity::Unipolar);
+        lfo.prepare(SAMPLE_RATE.into());
+        assert_eq!(run(&mut lfo, 4), [0.0, 0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn sample_and_hold_changes_once_per_cycle() {
+        let mut lfo = lfo(LfoShape::SampleAndHold);
+        lfo.prepare(SAMPLE_RATE.into());
+
+        let values = run(&mut lfo, 12);
+        for cycle in values.chunks(4) {
+            assert!(cycle.iter().all(|value| *value == cycle[0]));
+            assert!(cycle[0] >= -1.0 && cycle[0] < 1.0);
+        }
+        assert_ne!(values[0], values[4]);
+        assert_ne!(values[4], values[8]);
+    }
+
+    #[test]
+    fn sample_and_hold_draws_when_the_phase_shifts_across_a_cycle() {
+        let mut lfo = lfo(LfoShape::SampleAndHold);
+        lfo.prepare(SAMPLE_RATE.into());
+
+        let mut values = run(&mut lfo, 2);
+        LfophaseInput.set(&mut lfo, 0.75);
+        values.extend(run(&mut lfo, 4));
+        assert_eq!(values[0], values[1]);
+        assert_ne!(values[1], values[2]);
+        assert!(values[2..5].iter().all(|value| *value == values[2]));
+        assert_ne!(values[4], values[5]);
+
+        LfophaseInput.set(&mut lfo, 1.75);
+        let shifted = run(&mut lfo, 2);
+        assert_ne!(values[5], shifted[0]);
+        assert_eq!(shifted[0], shifted[1]);
+
+        LfophaseInput.set(&mut lfo, 1.5);
+        assert_eq!(run(&mut lfo, 1)[0], shifted[0]);
+    }
+
+    #[test]
+    fn default_lfos_run_once_prepared() {
+        let mut lfo = Lfo::default();
+        LforateInput.set(&mut lfo, 250.0);
+        lfo.prepare(SAMPLE_RATE.into());
+        assert_near(&run(&mut lfo, 4), &[0.0, 1.0, 0.0, -1.0]);
+
+        let mut lfo = Lfo {
+            shape: LfoShape::SampleAndHold,
+            ..Lfo::default()
+        };
+        LforateInput.set(&mut lfo, 250.0);
+        lfo.prepare(SAMPLE_RATE.into());
+        let values = run(&mut lfo, 8);
+        assert!(values.iter().all(|value| value.is_finite()));
+        assert_ne!(values[0], values[4]);
+    }
+
+    #[test]
+    fn tempo_sync_runs_a_cycle_every_so_many_beats() {
+        let mut lfo = Lfo::new(LfoShape::RisingSaw).with_beats(0.5);
+        LfotempoInput.set(&mut lfo, 7_500.0);
+        lfo.prepare(SAMPLE_RATE.into());
+
+        assert_eq!(run(&mut lfo, 4), [-1.0, -0.5, 0.0, 0.5]);
+        assert_eq!(run(&mut lfo, 1), [-1.0]);
+    }
+
+    #[test]
+    fn phase_offsets_shift_the_cycle() {
+        let mut lfo = lfo(LfoShape::RisingSaw);
+        LfophaseInput.set(&mut lfo, 0.5);
+        lfo.prepare(SAMPLE_RATE.into());
+        assert_eq!(run(&mut lfo, 2), [0.0, 0.5]);
+
+        LfophaseInput.set(&mut lfo, 0.25);
+        assert_eq!(run(&mut lfo, 2), [0.5, -1.0]);
+    }
+
+    #[test]
+    fn a_rising_reset_restarts_the_cycle() {
+        let mut lfo = lfo(LfoShape::RisingSaw);
+        lfo.prepare(SAMPLE_RATE.into());
+        run(&mut lfo, 3);
+
+        LforesetInput.set(&mut lfo, 1.0);
+        assert_eq!(run(&mut lfo, 3), [-1.0, -0.5, 0.0]);
+
+        LforesetInput.set(&mut lfo, 0.0);
+        run(&mut lfo, 1);
+        LforesetInput.set(&mut lfo, 1.0);
+        assert_eq!(run(&mut lfo, 1), [-1.0]);
+    }
+}
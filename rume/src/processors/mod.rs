@@ -1,5 +1,20 @@
 use crate::*;
 
+pub mod biquad;
+pub use biquad::*;
+
+pub mod svf;
+pub use svf::*;
+
+pub mod adsr;
+pub use adsr::*;
+
+pub mod lfo;
+pub use lfo::*;
+
+pub mod oscillators;
+pub use oscillators::*;
+
 #[processor]
 pub struct Value {
     #[output]
@@ -77,7 +92,7 @@ pub struct Saw {
 impl Processor for Saw {
     fn prepare(&mut self, config: AudioConfig) {
         self.sample_time = 1.0 / config.sample_rate as f32;
-        self.phasor.reset();
+        self.phasor = Phasor::with_max(1.0);
     }
 
     fn process(&mut self) {
@@ -85,7 +100,7 @@ impl Processor for Saw {
         self.phasor.inc(cps);
         let phase = self.phasor.advance();
         self.sample = waves::saw::rise(phase);
-        self.sample -= bandlimited::step((phase + 0.5) % 1., cps);
+        self.sample -= bandlimited::step(phase, cps);
         self.sample *= self.amplitude;
     }
 }
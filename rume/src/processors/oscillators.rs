@@ -0,0 +1,284 @@
+use crate::*;
+
+/// A band limited square wave: a pulse
+/// wave with a duty cycle of one half.
+#[processor]
+pub struct Square {
+    #[input]
+    frequency: f32,
+
+    #[input]
+    amplitude: f32,
+
+    #[output]
+    sample: f32,
+
+    phasor: Phasor,
+    sample_time: f32,
+}
+
+impl Square {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Processor for Square {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_time = 1.0 / config.sample_rate as f32;
+        self.phasor = Phasor::with_max(1.0);
+    }
+
+    fn process(&mut self) {
+        let cps = self.frequency * self.sample_time;
+        self.phasor.inc(cps);
+        let phase = self.phasor.advance();
+        self.sample = pulse(phase, 0.5, cps) * self.amplitude;
+    }
+}
+
+/// A band limited pulse wave, high for
+/// the `width` of each cycle, which can
+/// change at audio rate. `new` starts the
+/// width at a half; a `default` pulse has
+/// none, so it stays low until given one.
+#[processor]
+pub struct Pulse {
+    #[input]
+    frequency: f32,
+
+    #[input]
+    amplitude: f32,
+
+    #[input]
+    width: f32,
+
+    #[output]
+    sample: f32,
+
+    phasor: Phasor,
+    sample_time: f32,
+}
+
+impl Pulse {
+    pub fn new() -> Self {
+        Self {
+            width: 0.5,
+            ..Self::default()
+        }
+    }
+}
+
+impl Processor for Pulse {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_time = 1.0 / config.sample_rate as f32;
+        self.phasor = Phasor::with_max(1.0);
+    }
+
+    fn process(&mut self) {
+        let cps = self.frequency * self.sample_time;
+        self.phasor.inc(cps);
+        let phase = self.phasor.advance();
+        let width = self.width.clamp(0.0, 1.0);
+        self.sample = pulse(phase, width, cps) * self.amplitude;
+    }
+}
+
+/// A band limited triangle wave.
+#[processor]
+pub struct Triangle {
+    #[input]
+    frequency: f32,
+
+    #[input]
+    amplitude: f32,
+
+    #[output]
+    sample: f32,
+
+    phasor: Phasor,
+    sample_time: f32,
+}
+
+impl Triangle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Processor for Triangle {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_time = 1.0 / config.sample_rate as f32;
+        self.phasor = Phasor::with_max(1.0);
+    }
+
+    fn process(&mut self) {
+        let cps = self.frequency * self.sample_time;
+        self.phasor.inc(cps);
+        let phase = self.phasor.advance();
+        self.sample = waves::triangle(phase);
+        self.sample += 4. * cps * bandlimited::ramp(phase, cps);
+        self.sample -= 4. * cps * bandlimited::ramp((phase + 0.5) % 1., cps);
+        self.sample *= self.amplitude;
+    }
+}
+
+/// A pulse wave rising at the start of each
+/// cycle and falling `width` into it, with
+/// both edges smoothed.
+fn pulse(phase: f32, width: f32, cps: f32) -> f32 {
+    waves::pwm(phase, width) + bandlimited::step(phase, cps)
+        - bandlimited::step((phase + 1. - width) % 1., cps)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::f64::consts::PI;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// Windows of this many samples fit a whole
+    /// number of `CYCLES` of the test frequency,
+    /// so its harmonics land on multiples of
+    /// `CYCLES` in a DFT of the window, and the
+    /// harmonics that folded back from above
+    /// Nyquist land everywhere else.
+    const WINDOW: usize = 4_800;
+    const CYCLES: usize = 123;
+    const FREQUENCY: f32 = SAMPLE_RATE as f32 * CYCLES as f32 / WINDOW as f32;
+
+    fn render<P: Processor>(
+        mut processor: P,
+        frequency: impl Input<P>,
+        amplitude: impl Input<P>,
+        output: impl Output<P>,
+    ) -> Vec<f32> {
+        frequency.set(&mut processor, FREQUENCY);
+        amplitude.set(&mut processor, 1.0);
+        processor.prepare(SAMPLE_RATE.into());
+        (0..WINDOW)
+            .map(|_| {
+                processor.process();
+                output.get(&mut processor)
+            })
+            .collect()
+    }
+
+    fn naive(wave: impl Fn(f32) -> f32) -> Vec<f32> {
+        let mut phasor = Phasor::new(FREQUENCY / SAMPLE_RATE as f32, 1.0);
+        (0..WINDOW).map(|_| wave(phasor.advance())).collect()
+    }
+
+    /// The energy of `signal` that folded back from
+    /// above Nyquist, in decibels below its total.
+    fn aliasing(signal: &[f32]) -> f64 {
+        let twiddles: Vec<(f64, f64)> = (0..WINDOW)
+            .map(|n| 2.0 * PI * n as f64 / WINDOW as f64)
+            .map(|angle| (angle.cos(), angle.sin()))
+            .collect();
+
+        let (mut aliased, mut total) = (0.0, 0.0);
+        for bin in 1..WINDOW / 2 {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (n, x) in signal.iter().enumerate() {
+                let (cos, sin) = twiddles[(bin * n) % WINDOW];
+                re += *x as f64 * cos;
+                im -= *x as f64 * sin;
+            }
+            let energy = re * re + im * im;
+            total += energy;
+            if bin % CYCLES != 0 {
+                aliased += energy;
+            }
+        }
+        10.0 * (aliased / total).log10()
+    }
+
+    #[test]
+    fn pulses_are_high_for_their_width() {
+        let mut pulse = Pulse::new();
+        PulsefrequencyInput.set(&mut pulse, 1_000.0);
+        PulseamplitudeInput.set(&mut pulse, 1.0);
+        PulsewidthInput.set(&mut pulse, 0.25);
+        pulse.prepare(SAMPLE_RATE.into());
+
+        let cycle: Vec<f32> = (0..48)
+            .map(|_| {
+                pulse.process();
+                PulsesampleOutput.get(&mut pulse)
+            })
+            .collect();
+        assert!(cycle[2..10].iter().all(|x| *x == 1.0));
+        assert!(cycle[14..46].iter().all(|x| *x == -1.0));
+    }
+
+    #[test]
+    fn triangles_keep_their_peaks() {
+        let triangle = render(
+            Triangle::new(),
+            TrianglefrequencyInput,
+            TriangleamplitudeInput,
+            TrianglesampleOutput,
+        );
+        let peak = triangle.iter().fold(0.0_f32, |peak, x| peak.max(x.abs()));
+        assert!(peak > 0.9 && peak <= 1.0);
+    }
+
+    #[test]
+    fn band_limiting_reduces_aliasing() {
+        let cases = [
+            (
+                "square",
+                render(
+                    Square::new(),
+                    SquarefrequencyInput,
+                    SquareamplitudeInput,
+                    SquaresampleOutput,
+                ),
+                naive(waves::square),
+            ),
+            (
+                "pulse",
+                render(
+                    Pulse::new(),
+                    PulsefrequencyInput,
+                    PulseamplitudeInput,
+                    PulsesampleOutput,
+                ),
+                naive(waves::square),
+            ),
+            (
+                "triangle",
+                render(
+                    Triangle::new(),
+                    TrianglefrequencyInput,
+                    TriangleamplitudeInput,
+                    TrianglesampleOutput,
+                ),
+                naive(waves::triangle),
+            ),
+            (
+                "saw",
+                render(
+                    Saw::default(),
+                    SawfrequencyInput,
+                    SawamplitudeInput,
+                    SawsampleOutput,
+                ),
+                naive(waves::saw::rise),
+            ),
+        ];
+
+        for (name, bandlimited, naive) in cases.iter() {
+            let (bandlimited, naive) = (aliasing(bandlimited), aliasing(naive));
+            assert!(
+                bandlimited < naive - 10.0,
+                "{} aliases at {:.1} dB, {:.1} dB naively",
+                name,
+                bandlimited,
+                naive
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{convert::pitch, *};
+use core::f32::consts::PI;
+
+/// A zero-delay-feedback state-variable filter,
+/// with its lowpass, bandpass, highpass and notch
+/// responses on outputs of their own. Its inputs
+/// are the signal, the `cutoff` in hertz and the
+/// `q`. It stays stable however fast the cutoff
+/// moves, so it can be modulated at audio rate.
+#[processor]
+pub struct Svf {
+    #[input]
+    signal: f32,
+
+    #[input]
+    cutoff: f32,
+
+    #[input]
+    q: f32,
+
+    #[output]
+    lowpass: f32,
+
+    #[output]
+    bandpass: f32,
+
+    #[output]
+    highpass: f32,
+
+    #[output]
+    notch: f32,
+
+    sample_rate: f32,
+    settings: [f32; 2],
+    k: f32,
+    a: [f32; 3],
+    state: [f32; 2],
+}
+
+impl Svf {
+    pub fn new() -> Self {
+        Self {
+            cutoff: 1000.0,
+            q: core::f32::consts::FRAC_1_SQRT_2,
+            sample_rate: 48_000.0,
+            ..Self::default()
+        }
+    }
+
+    fn update(&mut self) {
+        let settings = [self.cutoff, self.q];
+        if settings == self.settings {
+            return;
+        }
+        self.settings = settings;
+
+        let cycles = pitch::to_cycles(self.cutoff, self.sample_rate);
+        let g = (PI * cycles.clamp(0.0, 0.499)).tan();
+        self.k = 1.0 / self.q.max(1e-3);
+
+        let a1 = 1.0 / (1.0 + g * (g + self.k));
+        self.a = [a1, g * a1, g * g * a1];
+    }
+}
+
+impl Processor for Svf {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.sample_rate = config.sample_rate as f32;
+        self.settings = [f32::NAN; 2];
+        self.state = [0.0; 2];
+        self.update();
+    }
+
+    fn process(&mut self) {
+        self.update();
+
+        let [a1, a2, a3] = self.a;
+        let [ic1, ic2] = self.state;
+
+        let v3 = self.signal - ic2;
+        let v1 = a1 * ic1 + a2 * v3;
+        let v2 = ic2 + a2 * ic1 + a3 * v3;
+        self.state = [2.0 * v1 - ic1, 2.0 * v2 - ic2];
+
+        self.lowpass = v2;
+        self.bandpass = v1;
+        self.highpass = self.signal - self.k * v1 - v2;
+        self.notch = self.lowpass + self.highpass;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// The gains of the lowpass, bandpass, highpass
+    /// and notch outputs of `svf` at `frequency`,
+    /// from the peaks of a settled sine.
+    fn gains_at(svf: &mut Svf, frequency: f32) -> [f32; 4] {
+        svf.prepare(SAMPLE_RATE.into());
+
+        let mut peaks = [0.0_f32; 4];
+        for n in 0..SAMPLE_RATE / 4 {
+            let phase = 2.0 * PI * frequency * n as f32 / SAMPLE_RATE as f32;
+            SvfsignalInput.set(svf, phase.sin());
+            svf.process();
+            if n > SAMPLE_RATE / 8 {
+                let outputs = [svf.lowpass, svf.bandpass, svf.highpass, svf.notch];
+                for (peak, output) in peaks.iter_mut().zip(outputs.iter()) {
+                    *peak = peak.max(output.abs());
+                }
+            }
+        }
+        peaks
+    }
+
+    fn svf(cutoff: f32, q: f32) -> Svf {
+        let mut svf = Svf::new();
+        SvfcutoffInput.set(&mut svf, cutoff);
+        SvfqInput.set(&mut svf, q);
+        svf
+    }
+
+    #[test]
+    fn each_output_has_its_response() {
+        let [low, band, high, notch] = gains_at(&mut svf(1000.0, 0.707), 100.0);
+        assert!((low - 1.0).abs() < 0.01);
+        assert!(band < 0.15 && high < 0.02);
+        assert!((notch - 1.0).abs() < 0.02);
+
+        let [low, band, high, notch] = gains_at(&mut svf(1000.0, 0.707), 10_000.0);
+        assert!(low < 0.02 && band < 0.15);
+        assert!((high - 1.0).abs() < 0.01);
+        assert!((notch - 1.0).abs() < 0.02);
+
+        // The bandpass peaks at `q`.
+        let [_, band, _, notch] = gains_at(&mut svf(1000.0, 2.0), 1000.0);
+        assert!((band - 2.0).abs() < 0.05);
+        assert!(notch < 0.01);
+    }
+
+    #[test]
+    fn outputs_are_reached_as_ports() {
+        let mut svf = svf(1000.0, 0.707);
+        svf.prepare(SAMPLE_RATE.into());
+        SvfsignalInput.set(&mut svf, 1.0);
+        svf.process();
+
+        assert_eq!(SvflowpassOutput.get(&mut svf), svf.lowpass);
+        assert_eq!(SvfbandpassOutput.get(&mut svf), svf.bandpass);
+        assert_eq!(SvfhighpassOutput.get(&mut svf), svf.highpass);
+        assert_eq!(SvfnotchOutput.get(&mut svf), svf.notch);
+    }
+
+    #[test]
+    fn stays_stable_under_fast_cutoff_modulation() {
+        let mut svf = svf(1000.0, 20.0);
+        svf.prepare(SAMPLE_RATE.into());
+
+        for n in 0..SAMPLE_RATE {
+            let sweep = (2.0 * PI * 3000.0 * n as f32 / SAMPLE_RATE as f32).sin();
+            SvfcutoffInput.set(&mut svf, 10_000.0 + 9_980.0 * sweep);
+            SvfsignalInput.set(&mut svf, if n % 64 < 32 { 1.0 } else { -1.0 });
+            svf.process();
+
+            for output in [svf.lowpass, svf.bandpass, svf.highpass, svf.notch].iter() {
+                assert!(output.is_finite() && output.abs() < 100.0);
+            }
+        }
+    }
+}
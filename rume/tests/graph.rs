@@ -0,0 +1,78 @@
+mod sampled {
+    rume::graph! {
+        inputs: {
+            level: { kind: sample_and_hold },
+            clock: { kind: trigger },
+        },
+        outputs: {
+            out: { capacity: 8 },
+        },
+        processors: {},
+        connections: {
+            clock.output ->  level.input,
+            level.output ->  out.input,
+        }
+    }
+}
+
+#[test]
+fn sample_and_hold_inputs_follow_their_trigger() {
+    use rume::{Processor, Renderable};
+
+    let (mut graph, mut inputs, mut outputs) = sampled::build();
+    graph.prepare(1_000.into());
+
+    inputs.level.push(0.5);
+    inputs.clock.push(1.0);
+    graph.render(2);
+    inputs.level.push(0.8);
+    graph.render(2);
+    inputs.clock.push(1.0);
+    graph.render(1);
+
+    let mut rendered = [0.0; 5];
+    outputs.out.read_into(&mut rendered);
+    assert_eq!(rendered, [0.5, 0.5, 0.5, 0.5, 0.8]);
+}
+
+mod resampled {
+    rume::graph! {
+        inputs: {
+            level: { kind: follow },
+            clock: { kind: follow },
+        },
+        outputs: {
+            out: { capacity: 8 },
+        },
+        processors: {
+            sampled: super::sampled::subgraph(),
+        },
+        connections: {
+            level.output      ->  sampled.input.0,
+            clock.output      ->  sampled.input.1,
+            sampled.output.0  ->  out.input,
+        }
+    }
+}
+
+#[test]
+fn subgraph_inputs_keep_their_options() {
+    use rume::{Processor, Renderable};
+
+    let (mut graph, mut inputs, mut outputs) = resampled::build();
+    graph.prepare(1_000.into());
+
+    inputs.level.push(0.5);
+    inputs.clock.push(1.0);
+    graph.render(2);
+    inputs.level.push(0.8);
+    graph.render(1);
+    inputs.clock.push(0.0);
+    graph.render(1);
+    inputs.clock.push(1.0);
+    graph.render(1);
+
+    let mut rendered = [0.0; 5];
+    outputs.out.read_into(&mut rendered);
+    assert_eq!(rendered, [0.5, 0.5, 0.5, 0.5, 0.8]);
+}
@@ -5,32 +5,62 @@
 /// Based of these papers:
 ///
 pub mod bandlimited {
-    #[cfg(not(feature = "std"))]
-    use crate::F32Extension;
-
     /// Second-Order Band Limited Step function.
+    /// The residual to subtract at phase `t` from a
+    /// naive waveform falling by 2.0 as its phase
+    /// wraps, advancing by `dt` every sample.
     #[inline(always)]
     pub fn step(t: f32, dt: f32) -> f32 {
         if t < dt {
-            (t / dt - 1.).powf(-2.)
+            let t = t / dt - 1.;
+            -t * t
         } else if t > 1. - dt {
-            ((t - 1.) / dt + 1.).powf(2.)
+            let t = (t - 1.) / dt + 1.;
+            t * t
         } else {
             0.
         }
     }
 
     /// Second-Order Band Limited Ramp function.
+    /// The residual to add at phase `t`, scaled by
+    /// `dt`, to a naive waveform whose slope per
+    /// cycle rises by 2.0 as its phase wraps.
     #[inline(always)]
     pub fn ramp(t: f32, dt: f32) -> f32 {
         if t < dt {
             let t = t / dt - 1.;
-            (-1.) / (3. * t.powf(3.))
-        } else if t > 1_f32 - dt {
-            let t = (t - 1.) / dt - 1.;
-            (1.0) / (3. * t.powf(3.))
+            -t * t * t / 3.
+        } else if t > 1. - dt {
+            let t = (t - 1.) / dt + 1.;
+            t * t * t / 3.
         } else {
             0.
         }
     }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        const DT: f32 = 0.1;
+
+        #[test]
+        fn step_meets_the_jump_halfway() {
+            assert_eq!(step(0.0, DT), -1.);
+            assert_eq!(step(1.0, DT), 1.);
+            assert_eq!(step(0.5, DT), 0.);
+            assert!(step(DT * 0.999, DT).abs() < 1e-5);
+            assert!(step(1. - DT * 0.999, DT).abs() < 1e-5);
+        }
+
+        #[test]
+        fn ramp_rounds_the_corner() {
+            assert!((ramp(0.0, DT) - 1. / 3.).abs() < 1e-6);
+            assert!((ramp(1.0, DT) - 1. / 3.).abs() < 1e-6);
+            assert_eq!(ramp(0.5, DT), 0.);
+            assert!(ramp(DT * 0.999, DT).abs() < 1e-5);
+            assert!(ramp(1. - DT * 0.999, DT).abs() < 1e-5);
+        }
+    }
 }
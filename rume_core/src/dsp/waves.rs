@@ -10,6 +10,11 @@ pub mod saw {
     }
 }
 
+#[inline(always)]
+pub fn triangle(phase: f32) -> f32 {
+    1. - (phase * 2. - 1.).abs() * 2.
+}
+
 #[inline(always)]
 pub fn square(phase: f32) -> f32 {
     pwm(phase, 0.5)
@@ -45,6 +50,15 @@ mod test {
         assert_eq!(saw::fall(1.0), -1.);
     }
 
+    #[test]
+    fn basic_triangle() {
+        assert_eq!(triangle(0.0), -1.);
+        assert_eq!(triangle(0.25), 0.);
+        assert_eq!(triangle(0.5), 1.);
+        assert_eq!(triangle(0.75), 0.);
+        assert_eq!(triangle(1.0), -1.);
+    }
+
     #[test]
     fn basic_square() {
         assert_eq!(square(0.0), 1.0);
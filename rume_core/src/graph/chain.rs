@@ -1,24 +1,148 @@
 use crate::{
-    graph::{io::*, proc::*, sort::*},
+    graph::{clock::*, dot::*, endpoints::*, error::*, io::*, node::*, proc::*, stream::*},
     lib::*,
 };
 
+#[cfg(feature = "std")]
+use crate::graph::edit::*;
+
 pub trait Renderable {
     fn render(&mut self, num_samples: usize);
 }
 
+/// A graph of processors that renders them in
+/// order. The chain owns its processors, which
+/// are reached through the `Handle`s returned
+/// when they were added.
 #[derive(Default)]
 pub struct SignalChain {
-    processors: ConnectedProcessors,
+    processors: Arena,
+    schedule: ConnectedProcessors,
     config: AudioConfig,
+    clock: Clock,
+    rendered_into: Option<usize>,
+    #[cfg(feature = "std")]
+    schedules: Option<Schedules>,
 }
 
-unsafe impl Send for SignalChain {}
+impl SignalChain {
+    /// The processor behind `handle`, if it
+    /// is in this chain and of that type.
+    pub fn processor<P: 'static>(&self, handle: Handle<P>) -> Option<&P> {
+        let processor = self.processors.get(handle.id().index())?.as_ref()?;
+        processor.as_any().downcast_ref()
+    }
+
+    pub fn processor_mut<P: 'static>(&mut self, handle: Handle<P>) -> Option<&mut P> {
+        let processor = self.processors.get_mut(handle.id().index())?.as_mut()?;
+        processor.as_any_mut().downcast_mut()
+    }
+
+    /// Render as many samples as `buffer` holds
+    /// frames, writing the frames that reach the
+    /// output endpoint read by `output` into
+    /// `buffer`, interleaved, instead of through
+    /// its stream. The endpoint keeps them in a
+    /// buffer sized when it is prepared, so a
+    /// larger `buffer` is rendered a block at a
+    /// time, without allocating. The chain renders
+    /// `buffer.len()` samples if no endpoint
+    /// feeds `output`, leaving `buffer` as it was.
+    pub fn render_into<T: Sample>(&mut self, output: &StreamConsumer<T>, buffer: &mut [T]) {
+        let index = match self.output_endpoint(output) {
+            Some(index) => index,
+            None => return self.render(buffer.len()),
+        };
+
+        let mut rendered = 0;
+        while let Some(endpoint) = self.endpoint_at(index, output) {
+            let channels = endpoint.channels();
+            let remaining = (buffer.len() - rendered) / channels;
+            let block = remaining.min(endpoint.capture_frames());
+            if block == 0 {
+                break;
+            }
+
+            endpoint.start_capture();
+            self.render(block);
+
+            let block = &mut buffer[rendered..rendered + block * channels];
+            if let Some(endpoint) = self.endpoint_at(index, output) {
+                endpoint.finish_capture(block);
+            }
+            rendered += block.len();
+        }
+    }
+
+    /// The index of the output endpoint feeding
+    /// `output`, remembered from the last call
+    /// so that it is only looked for when it
+    /// changes.
+    fn output_endpoint<T: Sample>(&mut self, output: &StreamConsumer<T>) -> Option<usize> {
+        if let Some(index) = self.rendered_into {
+            if self.endpoint_at(index, output).is_some() {
+                return Some(index);
+            }
+        }
+
+        let index = self.processors.iter_mut().position(|processor| {
+            processor
+                .as_mut()
+                .and_then(|processor| processor.as_any_mut().downcast_mut::<OutputEndpoint<T>>())
+                .is_some_and(|endpoint| endpoint.feeds(output))
+        })?;
+        self.rendered_into = Some(index);
+        Some(index)
+    }
+
+    fn endpoint_at<T: Sample>(
+        &mut self,
+        index: usize,
+        output: &StreamConsumer<T>,
+    ) -> Option<&mut OutputEndpoint<T>> {
+        let processor = self.processors.get_mut(index)?.as_mut()?;
+        let endpoint = processor.as_any_mut().downcast_mut::<OutputEndpoint<T>>()?;
+        endpoint.feeds(output).then_some(endpoint)
+    }
+
+    /// The sample clock of the chain, which
+    /// input endpoints time their events by.
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    /// The processors and connections of the
+    /// chain, in the order it renders them,
+    /// to be written out as Graphviz DOT text.
+    pub fn dot(&self) -> Dot<'_> {
+        Dot::new(&self.schedule)
+    }
+
+    /// Create an editor for this chain, to add
+    /// and remove processors and connections
+    /// while it renders. Creating another editor
+    /// disconnects the previous one.
+    #[cfg(feature = "std")]
+    pub fn editor(&mut self) -> SignalChainEditor {
+        let (editor, schedules) =
+            SignalChainEditor::new(self.schedule.topology(), self.processors.len(), self.config);
+        self.schedules = Some(schedules);
+        editor
+    }
+}
 
 impl Processor for SignalChain {
     fn prepare(&mut self, config: AudioConfig) {
-        self.config.buffer_size = config.buffer_size;
-        self.processors.prepare(config);
+        #[cfg(feature = "std")]
+        if let Some(schedules) = &mut self.schedules {
+            schedules.prepare(config, &mut self.processors, &mut self.schedule);
+        }
+
+        self.config = config;
+        self.schedule.allocate(config.buffer_size);
+        for processor in self.processors.iter_mut().flatten() {
+            processor.prepare(config);
+        }
     }
 
     fn process(&mut self) {
@@ -27,92 +151,196 @@ impl Processor for SignalChain {
 }
 
 impl Renderable for SignalChain {
+    /// Render the chain in blocks of at most
+    /// `AudioConfig::buffer_size` samples,
+    /// each processor rendering a whole block
+    /// before the next one runs.
     fn render(&mut self, num_samples: usize) {
-        (0..num_samples).for_each(|_| self.processors.process());
+        #[cfg(feature = "std")]
+        if let Some(schedules) = &mut self.schedules {
+            schedules.swap(&mut self.processors, &mut self.schedule);
+        }
+
+        let mut remaining = num_samples;
+        while remaining > 0 {
+            let block = remaining.min(self.schedule.block_size());
+            self.schedule.render(&mut self.processors, block);
+            self.clock.advance(block);
+            remaining -= block;
+        }
     }
 }
 
+/// Builds a `SignalChain` from processors
+/// and the connections between them.
+///
+/// The infallible methods hold on to the
+/// first error they run into, which `build`
+/// panics with and `try_build` returns.
 #[derive(Default)]
 pub struct SignalChainBuilder {
     chain: SignalChain,
+    error: Option<GraphError>,
 }
 
 impl SignalChainBuilder {
-    pub fn processor(mut self, processor: SharedDynProc) -> Self {
-        self.chain.processors.push(processor);
+    /// Hand a processor over to the chain. The
+    /// returned handle is used to connect it and
+    /// to reach it once the chain is built.
+    pub fn add<P>(&mut self, processor: P) -> Handle<P>
+    where
+        P: Processor + Send + 'static,
+    {
+        let id = NodeId(self.chain.processors.len());
+        let mut processor = Box::new(processor);
+
+        self.chain.schedule.push(id, processor.as_mut());
+        self.chain.processors.push(Some(processor));
+        Handle::new(id)
+    }
+
+    pub fn processor_mut<P: 'static>(&mut self, handle: Handle<P>) -> Option<&mut P> {
+        self.chain.processor_mut(handle)
+    }
+
+    /// The sample clock of the chain being built.
+    pub fn clock(&self) -> Clock {
+        self.chain.clock()
+    }
+
+    pub fn connection(mut self, output: OutputPort, input: InputPort) -> Self {
+        if let Err(error) = self.add_connection(output, input, false) {
+            self.error.get_or_insert(error);
+        }
         self
     }
 
-    pub fn connection(mut self, output: DynOutputPort, input: DynInputPort) -> Self {
-        self.chain
-            .processors
-            .find_mut(output.proc.clone())
-            .expect("Did not find this output processor in the chain")
-            .add_output(Connection::new(output, input));
+    pub fn try_connection(
+        mut self,
+        output: OutputPort,
+        input: InputPort,
+    ) -> Result<Self, GraphError> {
+        self.add_connection(output, input, false)?;
+        Ok(self)
+    }
+
+    /// Connect an output back to an input upstream
+    /// of it, closing a loop. The input receives
+    /// the data one block after it was produced.
+    pub fn feedback(mut self, output: OutputPort, input: InputPort) -> Self {
+        if let Err(error) = self.add_connection(output, input, true) {
+            self.error.get_or_insert(error);
+        }
         self
     }
 
-    pub fn build(mut self) -> SignalChain {
-        self.sort();
-        self.chain
+    pub fn try_feedback(
+        mut self,
+        output: OutputPort,
+        input: InputPort,
+    ) -> Result<Self, GraphError> {
+        self.add_connection(output, input, true)?;
+        Ok(self)
     }
 
-    fn sort(&mut self) {
-        self.chain
-            .processors
-            .order(TopologicalSort::reverse_sort(&self));
+    /// Choose how an input port of a processor
+    /// combines the data of several connections,
+    /// overriding the processor's own choice.
+    pub fn merge<P>(mut self, processor: Handle<P>, input: usize, merge: Merge) -> Self {
+        match self.chain.schedule.find_mut(processor.id()) {
+            Some(node) => node.set_merge(input, merge),
+            None => {
+                self.error.get_or_insert(GraphError::UnknownProcessor);
+            }
+        }
+        self
     }
-}
 
-impl Sortable for &mut SignalChainBuilder {
-    fn next_nodes(&self, index: usize) -> Vec<usize> {
-        self.chain
-            .processors
-            .get(index)
-            .unwrap()
-            .outs()
-            .iter()
-            .map(|con| {
-                self.chain
-                    .processors
-                    .index_of(con.output.proc.clone())
-                    .expect("Did not find this output processor in the chain")
-            })
-            .collect()
+    pub fn build(self) -> SignalChain {
+        match self.try_build() {
+            Ok(chain) => chain,
+            Err(error) => panic!("{}", error),
+        }
     }
 
-    fn num_nodes(&self) -> usize {
-        self.chain.processors.len()
+    pub fn try_build(mut self) -> Result<SignalChain, GraphError> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        self.chain.schedule.schedule()?;
+        Ok(self.chain)
+    }
+
+    fn add_connection(
+        &mut self,
+        output: OutputPort,
+        input: InputPort,
+        feedback: bool,
+    ) -> Result<(), GraphError> {
+        self.chain.schedule.connect(output, input, feedback)
     }
 }
 
 /// A short hand for adding a connection to
-/// a `SignalChainBuilder` and implicitly
-/// add processor.
+/// a `SignalChainBuilder` between processors
+/// added to it. A `~>` arrow adds a feedback
+/// connection instead.
 #[macro_export]
 macro_rules! connect {
-    ($builder:expr, ($out_proc:expr $(, $out_port_num:tt)*) => ($in_proc:expr $(, $in_port_num:tt)*)) => {
-        $builder = $builder
-            .processor($out_proc.clone())
-            .processor($in_proc.clone())
-            .connection(
-                $crate::make_output_port!($out_proc $(, $out_port_num)*),
-                $crate::make_input_port!($in_proc $(, $in_port_num)*),
-            );
+    ($builder:expr, ($($output:tt)*) => ($($input:tt)*)) => {
+        $builder = $builder.connection(
+            $crate::make_output_port!($($output)*),
+            $crate::make_input_port!($($input)*),
+        );
+    };
+    ($builder:expr, ($($output:tt)*) ~> ($($input:tt)*)) => {
+        $builder = $builder.feedback(
+            $crate::make_output_port!($($output)*),
+            $crate::make_input_port!($($input)*),
+        );
+    };
+}
+
+/// Adds each connection of a `chain!`
+/// declaration to a builder in turn.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! connect_all {
+    ($builder:ident $(,)?) => {};
+    ($builder:ident, ($($out:tt)*) => ($($in:tt)*) $(, $($rest:tt)*)?) => {
+        $crate::connect!($builder, ($($out)*) => ($($in)*));
+        $crate::connect_all!($builder $(, $($rest)*)?);
+    };
+    ($builder:ident, ($($out:tt)*) ~> ($($in:tt)*) $(, $($rest:tt)*)?) => {
+        $crate::connect!($builder, ($($out)*) ~> ($($in)*));
+        $crate::connect_all!($builder $(, $($rest)*)?);
     };
 }
 
 /// A short hand for creating a signal chain.
-/// This macro takes a series of output-to-input
-/// connections and constructs a `SignalChain`.
+/// This macro takes a builder the processors
+/// were added to, followed by a series of
+/// output-to-input connections between them,
+/// and constructs a `SignalChain`. Connections
+/// written with `~>` are feedback connections
+/// that may close a loop.
 #[macro_export]
 macro_rules! chain {
-    ( $( ($out_proc:expr $(, $out_port_num:tt)*) => ($in_proc:expr $(, $in_port_num:tt)*)),* ) => {{
-        let mut builder = $crate::SignalChainBuilder::default();
-        $(
-            $crate::connect!(builder, ($out_proc $(, $out_port_num)*) => ($in_proc $(, $in_port_num)*));
-        )*
-        builder.build()
+    ( $builder:ident, $($connections:tt)* ) => {{
+        $crate::connect_all!($builder, $($connections)*);
+        $builder.build()
+    }};
+}
+
+/// Like `chain!`, but returns a `Result`
+/// with the `GraphError` that stopped the
+/// chain from building instead of panicking.
+#[macro_export]
+macro_rules! try_chain {
+    ( $builder:ident, $($connections:tt)* ) => {{
+        $crate::connect_all!($builder, $($connections)*);
+        $builder.try_build()
     }};
 }
 
@@ -121,6 +349,24 @@ mod test {
     use super::*;
     use crate::proc::dummies::*;
 
+    #[derive(Default)]
+    struct RequiredInputProcessor {
+        input: (RequiredInput, RequiredInput),
+    }
+
+    impl Processor for RequiredInputProcessor {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {}
+
+        fn required_inputs(&self) -> &[usize] {
+            &[1]
+        }
+    }
+
+    input! { RequiredInputProcessor, RequiredInput,
+        |_: &mut RequiredInputProcessor, _: f32| {}
+    }
+
     #[test]
     fn empty_chain_does_not_panic() {
         let mut chain = SignalChainBuilder::default().build();
@@ -130,14 +376,19 @@ mod test {
 
     #[test]
     fn single_processor_chain_does_not_panic() {
-        let mut chain = SignalChainBuilder::default().processor(dummy()).build();
+        let mut builder = SignalChainBuilder::default();
+        builder.add(DummyProcessor::default());
+
+        let mut chain = builder.build();
         chain.prepare(48_000.into());
         chain.process();
     }
 
-    fn make_chain(num_processors: usize) -> (SignalChain, Vec<SharedProc<DummyProcessor>>) {
-        let processors = vec![dummy(); num_processors];
+    fn make_chain(num_processors: usize) -> (SignalChain, Vec<Handle<DummyProcessor>>) {
         let mut builder = SignalChainBuilder::default();
+        let processors: Vec<_> = (0..num_processors)
+            .map(|_| builder.add(DummyProcessor::default()))
+            .collect();
 
         for i in 0..processors.len() - 1 {
             connect!(builder, (processors[i]) => (processors[i + 1]));
@@ -162,18 +413,15 @@ mod test {
         chain.prepare(48_000.into());
 
         for proc in &processors {
-            let out = DummyOutput;
-            assert_eq!(out.get(proc.clone()), f32::default());
+            assert_eq!(value(&mut chain, *proc), f32::default());
         }
 
-        let input = DummyInput;
-        input.set(processors[0].clone(), VALUE_TO_PASS);
+        set_value(&mut chain, processors[0], VALUE_TO_PASS);
 
         chain.render(1);
 
         for proc in processors {
-            let out = DummyOutput;
-            assert_eq!(out.get(proc), VALUE_TO_PASS);
+            assert_eq!(value(&mut chain, proc), VALUE_TO_PASS);
         }
     }
 
@@ -187,21 +435,16 @@ mod test {
         chain.prepare(48_000.into());
 
         for proc in &processors {
-            let out = DummyOutput;
-            assert_eq!(out.get(proc.clone()), f32::default());
+            assert_eq!(value(&mut chain, *proc), f32::default());
         }
 
-        let input = DummyInput;
-        input.set(processors[2].clone(), VALUE_TO_INJECT);
-
-        let input = DummyInput;
-        input.set(processors[0].clone(), VALUE_TO_PASS);
+        set_value(&mut chain, processors[2], VALUE_TO_INJECT);
+        set_value(&mut chain, processors[0], VALUE_TO_PASS);
 
         chain.render(1);
 
         for proc in processors {
-            let out = DummyOutput;
-            assert_eq!(out.get(proc), VALUE_TO_PASS);
+            assert_eq!(value(&mut chain, proc), VALUE_TO_PASS);
         }
     }
 
@@ -210,10 +453,13 @@ mod test {
         const VALUE_TO_PASS: f32 = 1.0;
         const NUM_PROCESSORS: usize = 20;
 
-        let processors = vec![dummy(); NUM_PROCESSORS];
         let mut builder = SignalChainBuilder::default();
+        let mut processors: Vec<_> = (0..NUM_PROCESSORS)
+            .map(|_| builder.add(DummyProcessor::default()))
+            .collect();
+        processors.reverse();
 
-        DummyInput.set(processors[0].clone(), VALUE_TO_PASS);
+        DummyInput.set(builder.processor_mut(processors[0]).unwrap(), VALUE_TO_PASS);
 
         for i in 0..processors.len() - 1 {
             connect!(builder, (processors[i]) => (processors[i + 1]));
@@ -224,19 +470,73 @@ mod test {
         chain.render(1);
 
         for proc in processors {
-            assert_eq!(DummyOutput.get(proc), VALUE_TO_PASS);
+            assert_eq!(value(&mut chain, proc), VALUE_TO_PASS);
+        }
+    }
+
+    #[derive(Default)]
+    struct NestedInProcessor {
+        input: ((NestedFirstInput, NestedSecondInput), (NestedThirdInput,)),
+        values: [f32; 3],
+    }
+
+    impl Processor for NestedInProcessor {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {}
+    }
+
+    input! { NestedInProcessor, NestedFirstInput,
+        |proc: &mut NestedInProcessor, value: f32| {
+            proc.values[0] = value;
         }
     }
 
+    input! { NestedInProcessor, NestedSecondInput,
+        |proc: &mut NestedInProcessor, value: f32| {
+            proc.values[1] = value;
+        }
+    }
+
+    input! { NestedInProcessor, NestedThirdInput,
+        |proc: &mut NestedInProcessor, value: f32| {
+            proc.values[2] = value;
+        }
+    }
+
+    #[test]
+    fn nested_ports_are_told_apart_by_their_index() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let nested = builder.add(NestedInProcessor::default());
+
+        let mut chain = chain! { builder,
+            (a) => (nested, 0, 1 => 1),
+            (b) => (nested, 1, 0 => 2)
+        };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, a, 1.0);
+        set_value(&mut chain, b, 2.0);
+        chain.render(1);
+
+        assert_eq!(chain.processor(nested).unwrap().values, [0.0, 1.0, 2.0]);
+    }
+
     #[test]
     fn multiple_io_unsorted_gets_sorted() {
         const VALUE_TO_PASS: f32 = 1.0;
 
-        let (input, mid, output) = (dummy(), dummy(), dummy());
-        let multi_in = make_processor(MultiInProcessor::default());
-        let multi_out = make_processor(MultiOutProcessor::default());
+        let mut builder = SignalChainBuilder::default();
+        let output = builder.add(DummyProcessor::default());
+        let multi_in = builder.add(MultiInProcessor::default());
+        let mid = builder.add(DummyProcessor::default());
+        let multi_out = builder.add(MultiOutProcessor::default());
+        let input = builder.add(DummyProcessor::default());
 
-        let mut chain = chain! {
+        let mut chain = chain! { builder,
             (input) => (multi_out),
                        (multi_out, 0)  => (multi_in, 0),
                        (multi_out, 1)  => (mid),
@@ -246,11 +546,504 @@ mod test {
 
         chain.prepare(48_000.into());
 
-        DummyInput.set(input.clone(), VALUE_TO_PASS);
+        set_value(&mut chain, input, VALUE_TO_PASS);
+
+        chain.render(1);
+
+        assert_eq!(value(&mut chain, output), VALUE_TO_PASS);
+        assert_eq!(value(&mut chain, mid), VALUE_TO_PASS);
+    }
+
+    #[test]
+    fn block_processor_renders_between_per_sample_processors() {
+        const VALUE_TO_PASS: f32 = 1.5;
+
+        let mut builder = SignalChainBuilder::default();
+        let (input, output, sum) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let block = builder.add(BlockProcessorDummy::default());
+
+        let mut chain = chain! { builder,
+            (input) => (block, 0),
+                       (block, 0) => (output),
+                       (block, 1) => (sum)
+        };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, input, VALUE_TO_PASS);
+        chain.render(3);
+
+        assert_eq!(chain.processor(block).unwrap().num_blocks, 1);
+        assert_eq!(value(&mut chain, output), VALUE_TO_PASS * 2.0);
+        assert_eq!(value(&mut chain, sum), VALUE_TO_PASS);
+    }
+
+    #[test]
+    fn rendering_more_than_a_block_splits_it() {
+        const BLOCK_SIZE: usize = 4;
+        const NUM_SAMPLES: usize = 10;
+
+        let mut builder = SignalChainBuilder::default();
+        let counter = builder.add(CounterProcessor::default());
+        let block = builder.add(BlockProcessorDummy::default());
+        let output = builder.add(DummyProcessor::default());
+
+        let mut chain = chain! { builder,
+            (counter) => (block, 1),
+                         (block, 1) => (output)
+        };
+
+        chain.prepare(AudioConfig {
+            buffer_size: BLOCK_SIZE,
+            ..AudioConfig::default()
+        });
+        chain.render(NUM_SAMPLES);
+
+        assert_eq!(chain.processor(block).unwrap().num_blocks, 3);
+        assert_eq!(value(&mut chain, output), NUM_SAMPLES as f32);
+    }
+
+    #[test]
+    fn processors_connected_out_of_order_are_rendered_in_order() {
+        const VALUE_TO_PASS: f32 = 1.0;
+
+        let mut builder = SignalChainBuilder::default();
+        let (a, b, c) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+
+        connect!(builder, (b) => (c));
+        connect!(builder, (a) => (b));
+
+        let mut chain = builder.build();
+        chain.prepare(48_000.into());
+
+        set_value(&mut chain, a, VALUE_TO_PASS);
+        chain.render(1);
+
+        assert_eq!(value(&mut chain, c), VALUE_TO_PASS);
+    }
+
+    #[test]
+    #[should_panic(expected = "form a cycle")]
+    fn cycle_without_feedback_panics() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b, c) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+
+        let _ = chain! { builder,
+            (a) => (b),
+                   (b) => (c),
+                          (c) => (b)
+        };
+    }
+
+    #[test]
+    fn feedback_connection_is_delayed_by_a_block() {
+        const BLOCK_SIZE: usize = 4;
+
+        let mut builder = SignalChainBuilder::default();
+        let counter = builder.add(CounterProcessor::default());
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+
+        let mut chain = chain! { builder,
+            (counter) => (b),
+                         (b) ~> (a)
+        };
+
+        chain.prepare(AudioConfig {
+            buffer_size: BLOCK_SIZE,
+            ..AudioConfig::default()
+        });
+
+        chain.render(BLOCK_SIZE);
+        assert_eq!(value(&mut chain, a), 0.0);
+
+        chain.render(BLOCK_SIZE);
+        assert_eq!(value(&mut chain, a), BLOCK_SIZE as f32);
+
+        chain.render(BLOCK_SIZE);
+        assert_eq!(value(&mut chain, a), 2.0 * BLOCK_SIZE as f32);
+    }
+
+    #[test]
+    fn processor_can_feed_back_into_itself() {
+        let mut builder = SignalChainBuilder::default();
+        let dummy = builder.add(DummyProcessor::default());
+        let mut chain = chain! { builder, (dummy) ~> (dummy) };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, dummy, 1.0);
+        chain.render(1);
+
+        // The fed back value is still the
+        // silence from before the first block.
+        assert_eq!(value(&mut chain, dummy), 0.0);
+    }
+
+    #[test]
+    fn processors_are_reached_through_their_handles() {
+        let mut builder = SignalChainBuilder::default();
+        let dummy = builder.add(DummyProcessor::default());
+        let counter = builder.add(CounterProcessor::default());
+        let mut chain = builder.build();
+
+        assert!(chain.processor(dummy).is_some());
+        assert!(chain.processor(counter).is_some());
+
+        let mismatched: Handle<CounterProcessor> = Handle::new(dummy.id());
+        assert!(chain.processor(mismatched).is_none());
+        assert!(chain.processor_mut(mismatched).is_none());
+    }
+
+    #[test]
+    fn signal_chains_can_be_sent_to_another_thread() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SignalChain>();
+        assert_send::<SignalChainBuilder>();
+    }
+
+    #[test]
+    fn connecting_to_an_unknown_processor_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let a = builder.add(DummyProcessor::default());
+        // Added to another builder, where it is
+        // the second processor like `b` would be.
+        let b = {
+            let mut other = SignalChainBuilder::default();
+            other.add(DummyProcessor::default());
+            other.add(DummyProcessor::default())
+        };
+
+        let result = builder.try_connection(make_output_port!(a), make_input_port!(b));
+
+        assert_eq!(result.err(), Some(GraphError::UnknownProcessor));
+    }
+
+    #[test]
+    fn connecting_a_port_of_another_processor_type_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        // The second processor of another builder,
+        // with the same id as `b` but not its type.
+        let multi_in = {
+            let mut other = SignalChainBuilder::default();
+            other.add(DummyProcessor::default());
+            other.add(MultiInProcessor::default())
+        };
+
+        let result = builder
+            .try_connection(make_output_port!(a), make_input_port!(multi_in, 1))
+            .err();
+        assert_eq!(
+            result,
+            Some(GraphError::MismatchedPort {
+                processor: 1,
+                port: 1
+            })
+        );
+
+        let mut builder = SignalChainBuilder::default();
+        builder.add(DummyProcessor::default());
+        builder.add(DummyProcessor::default());
+        let result = builder
+            .try_connection(make_output_port!(multi_in), make_input_port!(b))
+            .err();
+        assert_eq!(
+            result,
+            Some(GraphError::MismatchedPort {
+                processor: 1,
+                port: 0
+            })
+        );
+    }
+
+    #[test]
+    fn connecting_twice_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let result = try_chain! { builder,
+            (a) => (b),
+            (a) => (b)
+        };
+
+        assert_eq!(
+            result.err(),
+            Some(GraphError::DuplicateConnection {
+                output: 0,
+                input: 1
+            })
+        );
+    }
+
+    #[test]
+    fn building_a_cycle_fails_with_its_processors() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b, c) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let result = try_chain! { builder,
+            (a) => (b),
+                   (b) => (c),
+                          (c) => (b)
+        };
+
+        assert_eq!(result.err(), Some(GraphError::Cycle(vec![1, 2])));
+    }
+
+    #[test]
+    fn leaving_a_required_input_unconnected_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let a = builder.add(DummyProcessor::default());
+        let required = builder.add(RequiredInputProcessor::default());
+
+        let result = try_chain! { builder, (a) => (required, 0) };
+        assert_eq!(
+            result.err(),
+            Some(GraphError::UnconnectedInput {
+                processor: 1,
+                port: 1
+            })
+        );
+
+        let mut builder = SignalChainBuilder::default();
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let required = builder.add(RequiredInputProcessor::default());
+
+        let result = try_chain! { builder, (a) => (required, 0), (b) => (required, 1) };
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn adding_a_block_processor_with_too_many_ports_fails() {
+        let mut builder = SignalChainBuilder::default();
+        builder.add(WideBlockDummy);
+
+        assert_eq!(
+            builder.try_build().err(),
+            Some(GraphError::TooManyPorts(max_block_ports() + 1))
+        );
+    }
+
+    #[test]
+    fn connecting_a_port_a_block_processor_does_not_declare_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let a = builder.add(DummyProcessor::default());
+        let block = builder.add(BlockProcessorDummy::default());
+
+        builder = builder
+            .try_connection(make_output_port!(a), make_input_port!(block, 1))
+            .unwrap();
+
+        let result = builder.try_connection(
+            OutputPort::of(block, 2, |proc| &proc.output.1),
+            make_input_port!(a),
+        );
+
+        assert_eq!(
+            result.err(),
+            Some(GraphError::PortOutOfRange {
+                processor: 1,
+                port: 2
+            })
+        );
+    }
+
+    fn make_fan_in(merge: Option<Merge>) -> f32 {
+        const VALUES: [f32; 3] = [2.0, -3.0, 4.0];
+
+        let mut builder = SignalChainBuilder::default();
+        let sources: Vec<_> = VALUES
+            .iter()
+            .map(|_| builder.add(DummyProcessor::default()))
+            .collect();
+        let output = builder.add(DummyProcessor::default());
+
+        for source in &sources {
+            connect!(builder, (*source) => (output));
+        }
+
+        if let Some(merge) = merge {
+            builder = builder.merge(output, 0, merge);
+        }
+
+        let mut chain = builder.build();
+        chain.prepare(48_000.into());
+
+        for (source, value) in sources.iter().zip(VALUES.iter()) {
+            set_value(&mut chain, *source, *value);
+        }
+        chain.render(1);
+
+        value(&mut chain, output)
+    }
+
+    #[test]
+    fn fan_in_connections_are_summed_by_default() {
+        assert_eq!(make_fan_in(None), 3.0);
+        assert_eq!(make_fan_in(Some(Merge::Sum)), 3.0);
+    }
+
+    #[test]
+    fn fan_in_connections_can_be_merged_differently() {
+        assert_eq!(make_fan_in(Some(Merge::Max)), 4.0);
+        assert_eq!(make_fan_in(Some(Merge::Multiply)), -24.0);
+    }
+
+    #[test]
+    fn fan_in_merged_as_last_keeps_the_last_processor_to_render() {
+        const VALUE_TO_PASS: f32 = 1.5;
+
+        let mut builder = SignalChainBuilder::default();
+        let (input, output) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let block = builder.add(BlockProcessorDummy::default());
+
+        connect!(builder, (input) => (output));
+        connect!(builder, (input) => (block, 0));
+        connect!(builder, (block, 0) => (output));
+
+        let mut chain = builder.merge(output, 0, Merge::Last).build();
+        chain.prepare(48_000.into());
+
+        set_value(&mut chain, input, VALUE_TO_PASS);
+        chain.render(1);
+
+        assert_eq!(value(&mut chain, output), VALUE_TO_PASS * 2.0);
+    }
+
+    #[test]
+    fn fan_in_into_a_block_processor_is_summed() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b, output) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let block = builder.add(BlockProcessorDummy::default());
+
+        let mut chain = chain! { builder,
+            (a) => (block, 0),
+            (b) => (block, 0),
+                   (block, 0) => (output)
+        };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, a, 1.0);
+        set_value(&mut chain, b, 2.0);
+        chain.render(1);
+
+        assert_eq!(value(&mut chain, output), 6.0);
+    }
+
+    #[test]
+    fn render_into_bypasses_the_output_stream() {
+        let (mut in_producer, in_consumer) = make_input_endpoint();
+        let (out_producer, out_consumer) = make_output_endpoint();
+
+        let mut builder = SignalChainBuilder::default();
+        let input = builder.add(InputEndpoint::new(in_consumer));
+        let output = builder.add(OutputEndpoint::new(out_producer));
+        let mut chain = chain! { builder, (input) => (output) };
+        chain.prepare(48_000.into());
+
+        in_producer.write_from(&[1.0, 2.0, 3.0, 4.0]);
+        let mut buffer = [0.0; 4];
+        chain.render_into(&out_consumer, &mut buffer);
+
+        assert_eq!(buffer, [1.0, 2.0, 3.0, 4.0]);
+        assert!(out_consumer.is_empty());
 
         chain.render(1);
+        assert_eq!(out_consumer.len(), 1);
+    }
+
+    #[test]
+    fn render_into_fills_buffers_larger_than_a_block() {
+        let (mut in_producer, in_consumer) = make_input_endpoint();
+        let (out_producer, out_consumer) = make_output_endpoint();
+
+        let mut builder = SignalChainBuilder::default();
+        let input = builder.add(InputEndpoint::new(in_consumer));
+        let output = builder.add(OutputEndpoint::new(out_producer).with_channels(2));
+        let mut chain = chain! { builder, (input) => (output, 0) };
+        chain.prepare(AudioConfig {
+            buffer_size: 4,
+            ..AudioConfig::default()
+        });
+
+        let values: Vec<f32> = (1..=10).map(|value| value as f32).collect();
+        in_producer.write_from(&values);
+        let mut buffer = [0.0; 21];
+        chain.render_into(&out_consumer, &mut buffer);
+
+        let left: Vec<f32> = buffer.chunks_exact(2).map(|frame| frame[0]).collect();
+        assert_eq!(left, values);
+        assert!(buffer.chunks_exact(2).all(|frame| frame[1] == 0.0));
+        assert_eq!(buffer[20], 0.0);
+        assert!(out_consumer.is_empty());
+        assert_eq!(chain.clock().now(), 10);
+    }
+
+    #[test]
+    fn events_land_on_their_sample_across_blocks() {
+        let (_, in_consumer) = make_input_endpoint();
+        let (mut events, event_consumer) = make_event_stream(8);
+        let (out_producer, out_consumer) = make_output_endpoint();
+
+        let mut builder = SignalChainBuilder::default();
+        let input = InputEndpointBuilder::new(in_consumer)
+            .events(event_consumer, builder.clock())
+            .build();
+        let input = builder.add(input);
+        let output = builder.add(OutputEndpoint::new(out_producer));
+        let mut chain = chain! { builder, (input) => (output) };
+        chain.prepare(AudioConfig {
+            buffer_size: 16,
+            ..AudioConfig::default()
+        });
+
+        let clock = chain.clock();
+        chain.render(8);
+        assert_eq!(clock.now(), 8);
+
+        events.push(Event::after(&clock, 5, 1.0));
+        events.push(Event::after(&clock, 21, 2.0));
+        events.push(Event::new(0, 3.0));
+        let mut buffer = [0.0; 24];
+        chain.render_into(&out_consumer, &mut buffer);
 
-        assert_eq!(DummyOutput.get(output), VALUE_TO_PASS);
-        assert_eq!(DummyOutput.get(mid), VALUE_TO_PASS);
+        let changes: Vec<_> = buffer
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0] != pair[1])
+            .map(|(index, pair)| (index + 1, pair[1]))
+            .collect();
+        assert_eq!(changes, [(5, 1.0), (21, 3.0)]);
+        assert_eq!(clock.now(), 32);
     }
 }
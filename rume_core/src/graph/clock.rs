@@ -0,0 +1,58 @@
+use crate::lib::*;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The sample clock of a `SignalChain`: the
+/// number of samples it rendered before the
+/// block it is rendering. Clones share the
+/// same count and can be read from any thread.
+///
+/// The count wraps around, so times are
+/// compared with `Clock::reached`.
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    now: Arc<AtomicUsize>,
+}
+
+impl Clock {
+    /// The time, in samples, at which the
+    /// next block of the chain starts, or
+    /// the current one started if it is
+    /// being rendered.
+    pub fn now(&self) -> usize {
+        self.now.load(Ordering::Acquire)
+    }
+
+    /// Whether `now` is at or after `time`,
+    /// allowing for the count wrapping.
+    pub fn reached(now: usize, time: usize) -> bool {
+        (now.wrapping_sub(time) as isize) >= 0
+    }
+
+    pub(crate) fn advance(&self, num_samples: usize) {
+        let now = self.now().wrapping_add(num_samples);
+        self.now.store(now, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_time() {
+        let clock = Clock::default();
+        let other = clock.clone();
+
+        clock.advance(64);
+        assert_eq!(other.now(), 64);
+    }
+
+    #[test]
+    fn reaching_a_time_allows_for_wrapping() {
+        assert!(Clock::reached(10, 10));
+        assert!(Clock::reached(11, 10));
+        assert!(!Clock::reached(9, 10));
+        assert!(Clock::reached(2, usize::MAX - 2));
+        assert!(!Clock::reached(usize::MAX - 2, 2));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::graph::proc::*;
+use core::fmt;
+
+/// The topology of a `SignalChain` as
+/// Graphviz DOT text, written out by
+/// its `Display` implementation.
+///
+/// Each processor is labelled with its
+/// type name, its node id and its place
+/// in the order the chain renders in.
+/// Each connection is labelled with the
+/// output and input port indices it
+/// joins. Feedback connections are dashed.
+///
+/// ```
+///     use rume_core::*;
+///
+///     #[derive(Default)]
+///     pub struct Dummy {
+///         pub input: DummyInput,
+///         pub output: DummyOutput,
+///     }
+///
+///     impl Processor for Dummy {
+///         fn prepare(&mut self, _: AudioConfig) {}
+///         fn process(&mut self) {}
+///     }
+///
+///     input! { Dummy, DummyInput, |_: &mut Dummy, _: f32| {} }
+///     output! { Dummy, DummyOutput, |_: &mut Dummy| -> f32 { 0.0 } }
+///
+///     let mut builder = SignalChainBuilder::default();
+///     let (a, b) = (builder.add(Dummy::default()), builder.add(Dummy::default()));
+///     let chain = chain! { builder, (b) => (a) };
+///
+///     let dot = chain.dot().to_string();
+///     assert!(dot.contains("n1 [label=\"Dummy #1\\norder 0\"];"));
+///     assert!(dot.contains("n1 -> n0 [label=\"0 -> 0\"];"));
+/// ```
+pub struct Dot<'a> {
+    schedule: &'a ConnectedProcessors,
+}
+
+impl<'a> Dot<'a> {
+    pub(crate) fn new(schedule: &'a ConnectedProcessors) -> Self {
+        Self { schedule }
+    }
+}
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "digraph SignalChain {{")?;
+        writeln!(f, "    node [shape=box];")?;
+
+        for (order, node) in self.schedule.iter().enumerate() {
+            writeln!(
+                f,
+                "    n{} [label=\"{} #{}\\norder {}\"];",
+                node.id().index(),
+                node.name(),
+                node.id().index(),
+                order
+            )?;
+        }
+
+        for node in self.schedule.iter() {
+            for con in node.outs() {
+                writeln!(
+                    f,
+                    "    n{} -> n{} [label=\"{} -> {}\"];",
+                    con.output.node().index(),
+                    con.input.node().index(),
+                    con.output.index(),
+                    con.input.index()
+                )?;
+            }
+
+            for con in node.feedback() {
+                writeln!(
+                    f,
+                    "    n{} -> n{} [label=\"{} -> {}\", style=dashed, constraint=false];",
+                    con.output.node().index(),
+                    con.input.node().index(),
+                    con.output.index(),
+                    con.input.index()
+                )?;
+            }
+        }
+
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{chain::*, proc::dummies::*};
+
+    #[test]
+    fn processors_are_listed_in_render_order() {
+        let mut builder = SignalChainBuilder::default();
+        let multi_in = builder.add(MultiInProcessor::default());
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+
+        let chain = chain! { builder,
+            (b) => (multi_in, 2),
+            (a) => (b)
+        };
+        let dot = chain.dot().to_string();
+
+        let lines: Vec<_> = dot.lines().collect();
+        assert_eq!(lines[0], "digraph SignalChain {");
+        assert_eq!(lines[2], "    n1 [label=\"DummyProcessor #1\\norder 0\"];");
+        assert_eq!(lines[3], "    n2 [label=\"DummyProcessor #2\\norder 1\"];");
+        assert_eq!(
+            lines[4],
+            "    n0 [label=\"MultiInProcessor #0\\norder 2\"];"
+        );
+        assert!(lines.contains(&"    n1 -> n2 [label=\"0 -> 0\"];"));
+        assert!(lines.contains(&"    n2 -> n0 [label=\"0 -> 2\"];"));
+        assert_eq!(lines.last(), Some(&"}"));
+    }
+
+    #[test]
+    fn feedback_connections_are_dashed() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+
+        let chain = chain! { builder,
+            (a) => (b),
+                   (b) ~> (a)
+        };
+        let dot = chain.dot().to_string();
+
+        assert!(dot.contains("    n1 -> n0 [label=\"0 -> 0\", style=dashed, constraint=false];"));
+    }
+}
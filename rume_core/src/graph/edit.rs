@@ -0,0 +1,562 @@
+use crate::{
+    graph::{error::*, io::*, node::*, proc::*},
+    lib::*,
+};
+use std::sync::{
+    mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    Mutex,
+};
+
+/// The number of schedules that can be
+/// committed before the chain picks
+/// any of them up.
+const MAX_PENDING_SCHEDULES: usize = 2;
+
+/// Edits the processors and connections of
+/// a `SignalChain` while it is rendering,
+/// from a thread other than the audio thread.
+///
+/// Edits are staged until `commit`, which sorts
+/// and links a new schedule for the chain on the
+/// calling thread. The chain swaps it in at the
+/// start of its next `render` call, so the audio
+/// thread never sorts or allocates, and hands the
+/// old schedule back to be dropped by the editor
+/// along with the processors that were removed.
+///
+/// Processors keep their state across a swap.
+/// Feedback connections restart from silence.
+///
+/// ```
+///     use rume_core::*;
+///
+///     #[derive(Default)]
+///     pub struct Dummy {
+///         pub input: DummyInput,
+///         pub output: DummyOutput,
+///         value: f32,
+///     }
+///
+///     impl Processor for Dummy {
+///         fn prepare(&mut self, _: AudioConfig) {}
+///         fn process(&mut self) {}
+///     }
+///
+///     input! { Dummy, DummyInput, |proc: &mut Dummy, value: f32| { proc.value = value; } }
+///     output! { Dummy, DummyOutput, |proc: &mut Dummy| -> f32 { proc.value } }
+///
+///     let mut builder = SignalChainBuilder::default();
+///     let a = builder.add(Dummy::default());
+///     let mut chain = builder.build();
+///     let mut editor = chain.editor();
+///
+///     let b = editor.add_processor(Dummy::default());
+///     editor.connect(make_output_port!(a), make_input_port!(b)).unwrap();
+///     editor.commit().unwrap();
+///
+///     std::thread::spawn(move || chain.render(64)).join().unwrap();
+/// ```
+pub struct SignalChainEditor {
+    topology: ConnectedProcessors,
+    added: Vec<(NodeId, Box<dyn AnyProcessor>)>,
+    removed: Vec<NodeId>,
+    next_id: usize,
+    updates: SyncSender<Update>,
+    retired: Receiver<Update>,
+    config: Arc<Mutex<AudioConfig>>,
+    in_flight: usize,
+}
+
+/// A schedule sent from an editor to its
+/// chain, along with the processors added
+/// for it. The chain sends it back holding
+/// the schedule and processors it replaced.
+struct Update {
+    schedule: ConnectedProcessors,
+    processors: Arena,
+    removed: Vec<NodeId>,
+}
+
+/// The end of an editor's channels
+/// held by the chain it edits.
+pub(crate) struct Schedules {
+    incoming: Receiver<Update>,
+    retired: SyncSender<Update>,
+    /// The updates replaced after the editor was
+    /// dropped, kept for `prepare` or `Drop` to
+    /// free away from the audio thread.
+    unsent: Vec<Update>,
+    config: Arc<Mutex<AudioConfig>>,
+}
+
+impl SignalChainEditor {
+    pub(crate) fn new(
+        topology: ConnectedProcessors,
+        num_processors: usize,
+        config: AudioConfig,
+    ) -> (Self, Schedules) {
+        let (updates, incoming) = sync_channel(MAX_PENDING_SCHEDULES);
+        let (retired, collected) = sync_channel(MAX_PENDING_SCHEDULES);
+        let config = Arc::new(Mutex::new(config));
+
+        let editor = Self {
+            topology,
+            added: Vec::new(),
+            removed: Vec::new(),
+            next_id: num_processors,
+            updates,
+            retired: collected,
+            config: config.clone(),
+            in_flight: 0,
+        };
+
+        let schedules = Schedules {
+            incoming,
+            retired,
+            unsent: Vec::with_capacity(MAX_PENDING_SCHEDULES),
+            config,
+        };
+
+        (editor, schedules)
+    }
+
+    /// Add a processor. It is prepared and handed
+    /// to the chain when the edits are committed.
+    pub fn add_processor<P>(&mut self, processor: P) -> Handle<P>
+    where
+        P: Processor + Send + 'static,
+    {
+        let id = NodeId(self.next_id);
+        let mut processor = Box::new(processor);
+
+        self.next_id += 1;
+        self.topology.push(id, processor.as_mut());
+        self.added.push((id, processor));
+        Handle::new(id)
+    }
+
+    /// Remove a processor along with every
+    /// connection to and from it.
+    pub fn remove_processor<P>(&mut self, processor: Handle<P>) -> Result<(), GraphError> {
+        let id = processor.id();
+        self.topology.remove(id)?;
+
+        match self.added.iter().position(|(added, _)| *added == id) {
+            Some(index) => drop(self.added.remove(index)),
+            None => self.removed.push(id),
+        }
+        Ok(())
+    }
+
+    pub fn connect(&mut self, output: OutputPort, input: InputPort) -> Result<(), GraphError> {
+        self.topology.connect(output, input, false)
+    }
+
+    /// Connect an output back to an input upstream
+    /// of it, closing a loop. The input receives
+    /// the data one block after it was produced.
+    pub fn feedback(&mut self, output: OutputPort, input: InputPort) -> Result<(), GraphError> {
+        self.topology.connect(output, input, true)
+    }
+
+    pub fn disconnect(&mut self, output: OutputPort, input: InputPort) -> Result<(), GraphError> {
+        self.topology.disconnect(output, input)
+    }
+
+    /// Choose how an input port of a processor
+    /// combines the data of several connections,
+    /// overriding the processor's own choice.
+    pub fn merge<P>(
+        &mut self,
+        processor: Handle<P>,
+        input: usize,
+        merge: Merge,
+    ) -> Result<(), GraphError> {
+        let node = self
+            .topology
+            .find_mut(processor.id())
+            .ok_or(GraphError::UnknownProcessor)?;
+        node.set_merge(input, merge);
+        Ok(())
+    }
+
+    /// Schedule the edits made so far and send them
+    /// to the chain. If the edits leave the graph
+    /// invalid or cannot be sent, the chain keeps its
+    /// current schedule and the edits stay staged.
+    pub fn commit(&mut self) -> Result<(), GraphError> {
+        self.collect_retired();
+        if self.in_flight == MAX_PENDING_SCHEDULES {
+            return Err(GraphError::PendingEdits);
+        }
+
+        // Hold the config until the update is sent, so a
+        // chain being prepared with another block size
+        // picks it up there rather than in `render`.
+        let config_lock = self.config.clone();
+        let config = config_lock.lock().unwrap();
+        let mut schedule = self.topology.topology();
+        schedule.schedule()?;
+        schedule.allocate(config.buffer_size);
+
+        let mut processors = Arena::new();
+        processors.resize_with(self.next_id, || None);
+        for (id, mut processor) in self.added.drain(..) {
+            processor.prepare(*config);
+            processors[id.index()] = Some(processor);
+        }
+
+        let update = Update {
+            schedule,
+            processors,
+            removed: core::mem::take(&mut self.removed),
+        };
+
+        match self.updates.try_send(update) {
+            Ok(()) => {
+                self.in_flight += 1;
+                Ok(())
+            }
+            Err(TrySendError::Full(update)) | Err(TrySendError::Disconnected(update)) => {
+                self.restage(update);
+                Err(GraphError::PendingEdits)
+            }
+        }
+    }
+
+    /// Stage the processors added and removed
+    /// by an update that was never sent again.
+    fn restage(&mut self, update: Update) {
+        self.removed = update.removed;
+        for (id, processor) in update.processors.into_iter().enumerate() {
+            if let Some(processor) = processor {
+                self.added.push((NodeId(id), processor));
+            }
+        }
+    }
+
+    /// Drop the schedules the chain has replaced,
+    /// along with the processors removed from it.
+    /// This also happens on every `commit`.
+    pub fn collect_retired(&mut self) {
+        while self.retired.try_recv().is_ok() {
+            self.in_flight -= 1;
+        }
+    }
+}
+
+impl Schedules {
+    /// Swap in the latest committed schedule, if
+    /// any, moving the processors it keeps over.
+    /// The editor never has more schedules in
+    /// flight than the retired channel holds, so
+    /// whatever is replaced is handed back to it.
+    /// Once the editor is dropped, no more than
+    /// `MAX_PENDING_SCHEDULES` updates are left
+    /// to arrive, and what they replace is kept
+    /// in `unsent` rather than dropped here.
+    #[inline]
+    pub(crate) fn swap(&mut self, processors: &mut Arena, schedule: &mut ConnectedProcessors) {
+        while let Ok(mut update) = self.incoming.try_recv() {
+            for (id, processor) in processors.iter_mut().enumerate() {
+                if !update.removed.contains(&NodeId(id)) {
+                    update.processors[id] = processor.take();
+                }
+            }
+
+            core::mem::swap(processors, &mut update.processors);
+            core::mem::swap(schedule, &mut update.schedule);
+            if let Err(TrySendError::Full(update)) | Err(TrySendError::Disconnected(update)) =
+                self.retired.try_send(update)
+            {
+                self.unsent.push(update);
+            }
+        }
+    }
+
+    /// Share the new config with the editor and
+    /// swap in the schedules committed with the
+    /// old one, for the chain to allocate them
+    /// along with the rest of its buffers. The
+    /// updates the editor never got back are
+    /// freed here.
+    pub(crate) fn prepare(
+        &mut self,
+        config: AudioConfig,
+        processors: &mut Arena,
+        schedule: &mut ConnectedProcessors,
+    ) {
+        let config_lock = self.config.clone();
+        let mut shared = config_lock.lock().unwrap();
+        *shared = config;
+        self.swap(processors, schedule);
+        self.unsent.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{chain::*, proc::dummies::*};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const VALUE: f32 = 1.0;
+
+    fn make_chain(num_processors: usize) -> (SignalChain, Vec<Handle<DummyProcessor>>) {
+        let mut builder = SignalChainBuilder::default();
+        let processors: Vec<_> = (0..num_processors)
+            .map(|_| builder.add(DummyProcessor::default()))
+            .collect();
+
+        for pair in processors.windows(2) {
+            connect!(builder, (pair[0]) => (pair[1]));
+        }
+
+        let mut chain = builder.build();
+        chain.prepare(48_000.into());
+        (chain, processors)
+    }
+
+    /// Raises its flag when dropped.
+    struct DropProcessor {
+        input: DropInput,
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Processor for DropProcessor {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {}
+    }
+
+    impl Drop for DropProcessor {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    input! { DropProcessor, DropInput, |_: &mut DropProcessor, _: f32| {} }
+
+    #[test]
+    fn added_processors_render_after_commit() {
+        let (mut chain, processors) = make_chain(2);
+        let mut editor = chain.editor();
+
+        let added = editor.add_processor(DummyProcessor::default());
+        editor
+            .connect(make_output_port!(processors[1]), make_input_port!(added))
+            .unwrap();
+
+        set_value(&mut chain, processors[0], VALUE);
+        chain.render(1);
+        assert!(chain.processor(added).is_none());
+
+        editor.commit().unwrap();
+        chain.render(1);
+        assert_eq!(value(&mut chain, added), VALUE);
+    }
+
+    #[test]
+    fn removed_processors_are_dropped_by_the_editor() {
+        let (mut chain, processors) = make_chain(1);
+        let mut editor = chain.editor();
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let removed = editor.add_processor(DropProcessor {
+            input: DropInput,
+            dropped: dropped.clone(),
+        });
+        editor
+            .connect(make_output_port!(processors[0]), make_input_port!(removed))
+            .unwrap();
+        editor.commit().unwrap();
+        chain.render(1);
+
+        editor.remove_processor(removed).unwrap();
+        editor.commit().unwrap();
+        chain.render(1);
+
+        assert!(chain.processor(removed).is_none());
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        editor.collect_retired();
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn processors_removed_after_the_editor_is_dropped_are_freed_in_prepare() {
+        let (mut chain, processors) = make_chain(1);
+        let mut editor = chain.editor();
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let removed = editor.add_processor(DropProcessor {
+            input: DropInput,
+            dropped: dropped.clone(),
+        });
+        editor
+            .connect(make_output_port!(processors[0]), make_input_port!(removed))
+            .unwrap();
+        editor.commit().unwrap();
+        chain.render(1);
+
+        editor.remove_processor(removed).unwrap();
+        editor.commit().unwrap();
+        drop(editor);
+        chain.render(1);
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        chain.prepare(48_000.into());
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn edits_that_cannot_be_sent_stay_staged() {
+        let (mut chain, _) = make_chain(1);
+        let mut editor = chain.editor();
+        let _ = chain.editor();
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        editor.add_processor(DropProcessor {
+            input: DropInput,
+            dropped: dropped.clone(),
+        });
+        assert_eq!(editor.commit(), Err(GraphError::PendingEdits));
+        assert!(!dropped.load(Ordering::SeqCst));
+        assert_eq!(editor.added.len(), 1);
+    }
+
+    #[test]
+    fn processors_removed_before_commit_are_never_sent() {
+        let (mut chain, _) = make_chain(1);
+        let mut editor = chain.editor();
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let removed = editor.add_processor(DropProcessor {
+            input: DropInput,
+            dropped: dropped.clone(),
+        });
+        editor.remove_processor(removed).unwrap();
+        assert!(dropped.load(Ordering::SeqCst));
+
+        editor.commit().unwrap();
+        chain.render(1);
+        assert!(chain.processor(removed).is_none());
+    }
+
+    #[test]
+    fn disconnected_processors_stop_receiving_data() {
+        let (mut chain, processors) = make_chain(2);
+        let mut editor = chain.editor();
+
+        editor
+            .disconnect(
+                make_output_port!(processors[0]),
+                make_input_port!(processors[1]),
+            )
+            .unwrap();
+        editor.commit().unwrap();
+
+        set_value(&mut chain, processors[0], VALUE);
+        chain.render(1);
+        assert_eq!(value(&mut chain, processors[1]), 0.0);
+
+        assert_eq!(
+            editor.disconnect(
+                make_output_port!(processors[0]),
+                make_input_port!(processors[1]),
+            ),
+            Err(GraphError::UnknownConnection {
+                output: 0,
+                input: 1
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_edits_keep_the_current_schedule() {
+        let (mut chain, processors) = make_chain(2);
+        let mut editor = chain.editor();
+
+        editor
+            .connect(
+                make_output_port!(processors[1]),
+                make_input_port!(processors[0]),
+            )
+            .unwrap();
+        assert!(matches!(editor.commit(), Err(GraphError::Cycle(_))));
+
+        set_value(&mut chain, processors[0], VALUE);
+        chain.render(1);
+        assert_eq!(value(&mut chain, processors[1]), VALUE);
+    }
+
+    #[test]
+    fn adding_a_block_processor_with_too_many_ports_fails() {
+        let (mut chain, _) = make_chain(1);
+        let mut editor = chain.editor();
+
+        editor.add_processor(WideBlockDummy);
+        assert_eq!(
+            editor.commit(),
+            Err(GraphError::TooManyPorts(max_block_ports() + 1))
+        );
+        chain.render(1);
+    }
+
+    #[test]
+    fn commits_fail_until_the_chain_picks_them_up() {
+        let (mut chain, _) = make_chain(2);
+        let mut editor = chain.editor();
+
+        for _ in 0..MAX_PENDING_SCHEDULES {
+            editor.commit().unwrap();
+        }
+        assert_eq!(editor.commit(), Err(GraphError::PendingEdits));
+
+        chain.render(1);
+        editor.commit().unwrap();
+    }
+
+    #[test]
+    fn edits_committed_before_a_prepare_use_its_block_size() {
+        const BLOCK_SIZE: usize = 512;
+
+        let (mut chain, processors) = make_chain(1);
+        let mut editor = chain.editor();
+
+        let block = editor.add_processor(BlockProcessorDummy::default());
+        editor
+            .connect(make_output_port!(processors[0]), make_input_port!(block, 0))
+            .unwrap();
+        editor.commit().unwrap();
+
+        chain.prepare(AudioConfig {
+            buffer_size: BLOCK_SIZE,
+            ..AudioConfig::default()
+        });
+        chain.render(BLOCK_SIZE);
+
+        assert_eq!(chain.processor(block).unwrap().num_blocks, 1);
+    }
+
+    #[test]
+    fn edits_are_picked_up_on_the_rendering_thread() {
+        let (mut chain, processors) = make_chain(1);
+        let mut editor = chain.editor();
+
+        let added = editor.add_processor(DummyProcessor::default());
+        editor
+            .connect(make_output_port!(processors[0]), make_input_port!(added))
+            .unwrap();
+        editor.commit().unwrap();
+
+        set_value(&mut chain, processors[0], VALUE);
+        let mut chain = std::thread::spawn(move || {
+            chain.render(1);
+            chain
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(value(&mut chain, added), VALUE);
+    }
+}
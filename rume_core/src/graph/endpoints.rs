@@ -1,96 +1,527 @@
-use crate::*;
-use core::{ops::Range, option::Option};
-pub use heapless;
-pub use heapless::{
-    consts::*,
-    spsc::{Consumer, Producer, Queue},
+use crate::{
+    convert::{db, tick},
+    lib::Vec,
+    *,
 };
+use core::{fmt::Debug, ops::Range, option::Option};
+pub use heapless;
+pub use heapless::consts::*;
 
 pub type StreamDataType = f32;
 
-pub type OutputStreamSize = U2048;
-pub type OutputStream = Queue<StreamDataType, OutputStreamSize>;
-pub type OutputStreamConsumer = Consumer<'static, StreamDataType, OutputStreamSize>;
-pub type OutputStreamProducer = Producer<'static, StreamDataType, OutputStreamSize>;
+pub const OUTPUT_STREAM_CAPACITY: usize = 2048;
+pub type OutputStreamConsumer<T = StreamDataType> = StreamConsumer<T>;
+pub type OutputStreamProducer<T = StreamDataType> = StreamProducer<T>;
+
+pub const INPUT_STREAM_CAPACITY: usize = 256;
+pub type InputStreamConsumer<T = StreamDataType> = StreamConsumer<T>;
+pub type InputStreamProducer<T = StreamDataType> = StreamProducer<T>;
 
-pub type InputStreamSize = U256;
-pub type InputStream = Queue<StreamDataType, InputStreamSize>;
-pub type InputStreamConsumer = Consumer<'static, StreamDataType, InputStreamSize>;
-pub type InputStreamProducer = Producer<'static, StreamDataType, InputStreamSize>;
+pub type EventConsumer = StreamConsumer<Event>;
+pub type EventProducer = StreamProducer<Event>;
 
-pub struct OutputEndpoint {
-    pub input: OutputEndpointInput,
-    stream: OutputStreamProducer,
+/// A value for an input endpoint to take
+/// on the sample at `time` on the clock of
+/// its chain, or as soon as it can if that
+/// sample is already rendered. Events are
+/// to be pushed in the order of their times.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub time: usize,
+    pub value: f32,
 }
 
-input! { OutputEndpoint, OutputEndpointInput,
-    |proc: &mut OutputEndpoint, value: f32| {
-        proc.stream.enqueue(value).unwrap();
+impl Event {
+    pub fn new(time: usize, value: f32) -> Self {
+        Self { time, value }
+    }
+
+    /// An event `offset` samples into the
+    /// next block `clock` renders, for hosts
+    /// passing events along with a block.
+    pub fn after(clock: &Clock, offset: usize, value: f32) -> Self {
+        Self::new(clock.now().wrapping_add(offset), value)
     }
 }
 
-impl OutputEndpoint {
-    pub fn new(stream: OutputStreamProducer) -> Self {
+/// Create an event producer and consumer
+/// sharing a queue of `capacity` events.
+pub fn make_event_stream(capacity: usize) -> (EventProducer, EventConsumer) {
+    stream(capacity)
+}
+
+/// The events of an input endpoint, read
+/// against the clock of its chain.
+struct EventReader {
+    events: EventConsumer,
+    clock: Clock,
+    pending: Option<Event>,
+    block: usize,
+    offset: usize,
+}
+
+impl EventReader {
+    fn new(events: EventConsumer, clock: Clock) -> Self {
         Self {
-            input: OutputEndpointInput,
+            events,
+            block: clock.now(),
+            clock,
+            pending: None,
+            offset: 0,
+        }
+    }
+
+    /// The time of the sample being rendered,
+    /// counting samples into the current block.
+    fn tick(&mut self) -> usize {
+        let block = self.clock.now();
+        if block != self.block {
+            self.block = block;
+            self.offset = 0;
+        }
+        self.offset += 1;
+        block.wrapping_add(self.offset - 1)
+    }
+
+    fn due(&mut self, now: usize) -> Option<f32> {
+        if self.pending.is_none() {
+            self.pending = self.events.dequeue();
+        }
+
+        match self.pending {
+            Some(event) if Clock::reached(now, event.time) => {
+                self.pending = None;
+                Some(event.value)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A type of value that endpoints can
+/// stream in and out of a graph, which
+/// renders in `f32`. Integer samples map
+/// `-1.0..=1.0` onto their full range.
+pub trait Sample: Copy + Default + Debug + Send + 'static {
+    fn from_f32(value: f32) -> Self;
+    fn to_f32(self) -> f32;
+}
+
+impl Sample for f32 {
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl Sample for f64 {
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+macro_rules! integer_sample {
+    ($($int:ty),*) => {$(
+        impl Sample for $int {
+            #[inline(always)]
+            fn from_f32(value: f32) -> Self {
+                (value.max(-1.0).min(1.0) as f64 * <$int>::MAX as f64) as $int
+            }
+
+            #[inline(always)]
+            fn to_f32(self) -> f32 {
+                (self as f64 / <$int>::MAX as f64) as f32
+            }
+        }
+    )*};
+}
+
+integer_sample!(i16, i32);
+
+/// Streams what reaches its inputs out of the
+/// graph, one frame per sample with a value
+/// per channel. It is mono unless given more
+/// channels, each with its own input port:
+/// `input.0`, `input.1`... `input` itself is
+/// the port of the first channel.
+pub struct OutputEndpoint<T: Sample = StreamDataType> {
+    pub input: OutputEndpointInputs,
+    stream: OutputStreamProducer<T>,
+    frame: [T; MAX_CHANNELS],
+    channels: usize,
+    follow_config: bool,
+    capture: Vec<T>,
+    captured: usize,
+    capturing: bool,
+}
+
+/// The input of the first channel,
+/// the only one of a mono endpoint.
+#[derive(Debug, Default, Clone)]
+pub struct OutputEndpointInput;
+
+impl<T: Sample> Input<OutputEndpoint<T>> for OutputEndpointInput {
+    fn set(&self, proc: &mut OutputEndpoint<T>, value: f32) {
+        proc.set_channel(0, value);
+    }
+}
+
+/// The input of channel `C`.
+#[derive(Debug, Default, Clone)]
+pub struct OutputEndpointChannel<const C: usize>;
+
+impl<T: Sample, const C: usize> Input<OutputEndpoint<T>> for OutputEndpointChannel<C> {
+    fn set(&self, proc: &mut OutputEndpoint<T>, value: f32) {
+        proc.set_channel(C, value);
+    }
+}
+
+/// The input ports of an `OutputEndpoint`, one
+/// field per channel it can have, so that they
+/// are reached as `input.0`, `input.1`...
+#[rustfmt::skip]
+#[derive(Debug, Default, Clone)]
+pub struct OutputEndpointInputs(
+    pub OutputEndpointInput,        pub OutputEndpointChannel<1>,
+    pub OutputEndpointChannel<2>,   pub OutputEndpointChannel<3>,
+    pub OutputEndpointChannel<4>,   pub OutputEndpointChannel<5>,
+    pub OutputEndpointChannel<6>,   pub OutputEndpointChannel<7>,
+    pub OutputEndpointChannel<8>,   pub OutputEndpointChannel<9>,
+    pub OutputEndpointChannel<10>,  pub OutputEndpointChannel<11>,
+    pub OutputEndpointChannel<12>,  pub OutputEndpointChannel<13>,
+    pub OutputEndpointChannel<14>,  pub OutputEndpointChannel<15>,
+);
+
+impl<T: Sample> Input<OutputEndpoint<T>> for OutputEndpointInputs {
+    fn set(&self, proc: &mut OutputEndpoint<T>, value: f32) {
+        proc.set_channel(0, value);
+    }
+}
+
+impl<T: Sample> OutputEndpoint<T> {
+    pub fn new(stream: OutputStreamProducer<T>) -> Self {
+        Self {
+            input: OutputEndpointInputs::default(),
             stream,
+            frame: [T::default(); MAX_CHANNELS],
+            channels: 1,
+            follow_config: false,
+            capture: Vec::new(),
+            captured: 0,
+            capturing: false,
+        }
+    }
+
+    /// Stream `channels` channels, up to
+    /// `MAX_CHANNELS`, instead of one.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.set_channels(channels);
+        self.follow_config = false;
+        self
+    }
+
+    /// Stream as many channels as the
+    /// `AudioConfig` the endpoint is
+    /// prepared with has.
+    pub fn with_config_channels(mut self) -> Self {
+        self.follow_config = true;
+        self
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn set_channels(&mut self, channels: usize) {
+        self.stream.set_channels(channels);
+        self.channels = self.stream.channels();
+    }
+
+    fn set_channel(&mut self, channel: usize, value: f32) {
+        if channel < self.channels {
+            self.frame[channel] = T::from_f32(value);
+        }
+    }
+
+    pub(crate) fn feeds(&self, consumer: &OutputStreamConsumer<T>) -> bool {
+        self.stream.feeds(consumer)
+    }
+
+    /// The number of frames that can be kept
+    /// aside at once, a block of the config
+    /// the endpoint was prepared with.
+    pub(crate) fn capture_frames(&self) -> usize {
+        self.capture.len() / self.channels
+    }
+
+    /// Keep the next frames aside instead of
+    /// pushing them, as long as they fit.
+    pub(crate) fn start_capture(&mut self) {
+        self.captured = 0;
+        self.capturing = true;
+    }
+
+    /// Copy the values kept aside into `buffer`
+    /// and go back to pushing them.
+    pub(crate) fn finish_capture(&mut self, buffer: &mut [T]) {
+        let captured = self.captured.min(buffer.len());
+        buffer[..captured].copy_from_slice(&self.capture[..captured]);
+        self.capturing = false;
+    }
+
+    /// The number of frames that did not
+    /// fit in the stream of this endpoint.
+    pub fn overflows(&self) -> usize {
+        self.stream.overflows()
+    }
+
+    /// The number of values read from
+    /// the stream of this endpoint while
+    /// it was empty.
+    pub fn underruns(&self) -> usize {
+        self.stream.underruns()
+    }
+}
+
+impl<T: Sample> Processor for OutputEndpoint<T> {
+    fn prepare(&mut self, config: AudioConfig) {
+        if self.follow_config {
+            self.set_channels(config.num_channels);
+        }
+        self.capture.clear();
+        self.capture
+            .resize(config.buffer_size * self.channels, T::default());
+    }
+
+    fn process(&mut self) {
+        let frame = &mut self.frame[..self.channels];
+        let end = self.captured + frame.len();
+        if self.capturing && end <= self.capture.len() {
+            self.capture[self.captured..end].copy_from_slice(frame);
+            self.captured = end;
+        } else {
+            self.stream.push_frame(frame);
         }
+        frame.iter_mut().for_each(|value| *value = T::default());
     }
 }
 
-impl Processor for OutputEndpoint {
-    fn prepare(&mut self, _: AudioConfig) {}
-    fn process(&mut self) {}
+macro_rules! output_endpoint_ports {
+    ($processor:ident, $index:ident, $($channel:tt),*) => {
+        match $index {
+            $($channel => Some(make_input_port!($processor, $channel)),)*
+            _ => None,
+        }
+    };
+}
+
+impl<T: Sample> IndexedPorts for OutputEndpoint<T> {
+    fn input_port(processor: Handle<Self>, index: usize) -> Option<InputPort> {
+        output_endpoint_ports!(
+            processor, index, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15
+        )
+    }
+
+    fn output_port(_: Handle<Self>, _: usize) -> Option<OutputPort> {
+        None
+    }
 }
 
-pub struct InputEndpoint {
+pub struct InputEndpoint<T: Sample = StreamDataType> {
+    pub input: InputEndpointTrigger,
+    pub signal: InputEndpointSignal,
     pub output: InputEndpointOutput,
-    stream: InputStreamConsumer,
+    stream: InputStreamConsumer<T>,
+    connected: f32,
+    arrived: Option<f32>,
+    received: f32,
     value: f32,
     range: Option<RangedData>,
+    taper: Option<Taper>,
     smooth: Option<ValueSmoother>,
     kind: InputEndpointKind,
+    events: Option<EventReader>,
+    pulse: u32,
+    held: u32,
+    open: bool,
+    retrigger: bool,
+    trigger: f32,
+    triggered: bool,
+}
+
+/// The trigger of a `SampleAndHold` endpoint,
+/// which outputs the last value it received
+/// each time this rises above zero.
+#[derive(Debug, Default, Clone)]
+pub struct InputEndpointTrigger;
+
+impl<T: Sample> Input<InputEndpoint<T>> for InputEndpointTrigger {
+    fn set(&self, proc: &mut InputEndpoint<T>, value: f32) {
+        proc.trigger = value;
+    }
 }
 
-output! { InputEndpoint, InputEndpointOutput,
-    |proc: &mut InputEndpoint| -> f32 {
+/// Values sent to an endpoint through a
+/// connection rather than its stream, as when
+/// the inlet of a subgraph feeds it. Each time
+/// the signal changes, the endpoint receives
+/// it as if it had been pushed to the stream.
+#[derive(Debug, Default, Clone)]
+pub struct InputEndpointSignal;
+
+impl<T: Sample> Input<InputEndpoint<T>> for InputEndpointSignal {
+    fn set(&self, proc: &mut InputEndpoint<T>, value: f32) {
+        if value != proc.connected {
+            proc.connected = value;
+            proc.arrived = Some(value);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct InputEndpointOutput;
+
+impl<T: Sample> Output<InputEndpoint<T>> for InputEndpointOutput {
+    fn get(&self, proc: &mut InputEndpoint<T>) -> f32 {
         proc.value
     }
 }
 
-impl InputEndpoint {
-    pub fn new(stream: InputStreamConsumer) -> Self {
+impl<T: Sample> InputEndpoint<T> {
+    pub fn new(stream: InputStreamConsumer<T>) -> Self {
         Self {
+            input: InputEndpointTrigger,
+            signal: InputEndpointSignal,
             output: InputEndpointOutput,
             stream,
+            connected: 0.0,
+            arrived: None,
+            received: 0.0,
             value: 0.0,
             range: None,
+            taper: None,
             smooth: None,
             kind: InputEndpointKind::Follow,
+            events: None,
+            pulse: 1,
+            held: 0,
+            open: false,
+            retrigger: false,
+            trigger: 0.0,
+            triggered: false,
         }
     }
+
+    /// The number of values that did not
+    /// fit in the stream of this endpoint.
+    pub fn overflows(&self) -> usize {
+        self.stream.overflows()
+    }
+
+    /// The number of values read from
+    /// the stream of this endpoint while
+    /// it was empty.
+    pub fn underruns(&self) -> usize {
+        self.stream.underruns()
+    }
 }
 
-impl Processor for InputEndpoint {
-    fn prepare(&mut self, _: AudioConfig) {
-        self.set_value(self.value);
+impl<T: Sample> Processor for InputEndpoint<T> {
+    fn prepare(&mut self, config: AudioConfig) {
+        // Momentary kinds start closed rather than
+        // firing again with what they last received.
+        self.value = match self.kind {
+            InputEndpointKind::Follow
+            | InputEndpointKind::Latch
+            | InputEndpointKind::SampleAndHold => self.map_value(self.received),
+            _ => 0.0,
+        };
+        if let Some(smooth) = self.smooth.as_mut() {
+            smooth.prepare(config.sample_rate);
+            smooth.set(self.value, self.value);
+        }
+
+        self.pulse = match self.kind {
+            InputEndpointKind::Pulse(samples) => samples,
+            InputEndpointKind::PulseMillis(ms) => {
+                libm::roundf(tick::from_millis(ms, config.sample_rate as f32)) as u32
+            }
+            _ => 1,
+        }
+        .max(1);
+        self.held = 0;
+        self.open = false;
+        self.retrigger = false;
+        self.triggered = self.trigger > 0.0;
+        self.connected = self.received;
+        self.arrived = None;
     }
 
     fn process(&mut self) {
-        match self.stream.dequeue() {
-            Some(value) => self.set_value(value),
-            None => self.process_value(),
+        let now = self.events.as_mut().map(EventReader::tick);
+        let mut due = false;
+        while let Some(value) = now.and_then(|now| self.events.as_mut()?.due(now)) {
+            self.receive(value);
+            due = true;
+        }
+
+        let arrived = match self.stream.dequeue() {
+            Some(value) => Some(value.to_f32()),
+            None => self.arrived.take(),
+        };
+
+        match arrived {
+            Some(value) => self.receive(value),
+            None if !due => self.process_value(),
+            None => {}
+        }
+
+        if self.kind == InputEndpointKind::SampleAndHold {
+            self.sample_on_trigger();
         }
     }
 }
 
-impl InputEndpoint {
-    fn set_value(&mut self, value: f32) {
-        let mut new_value = value;
+impl<T: Sample> IndexedPorts for InputEndpoint<T> {
+    fn input_port(processor: Handle<Self>, index: usize) -> Option<InputPort> {
+        match index {
+            0 => Some(make_input_port!(processor)),
+            1 => Some(InputPort::of(processor, 1, |proc| &proc.signal)),
+            _ => None,
+        }
+    }
 
-        if let Some(range) = self.range.as_mut() {
-            new_value = (*range).clamp(new_value);
+    fn output_port(processor: Handle<Self>, index: usize) -> Option<OutputPort> {
+        match index {
+            0 => Some(make_output_port!(processor)),
+            _ => None,
         }
+    }
+}
+
+impl<T: Sample> InputEndpoint<T> {
+    fn map_value(&mut self, value: f32) -> f32 {
+        match (self.taper, self.range.as_mut()) {
+            (Some(taper), Some(range)) => taper.map(value, &range.range),
+            (Some(taper), None) => taper.map(value, &(0.0..1.0)),
+            (None, Some(range)) => (*range).clamp(value),
+            (None, None) => value,
+        }
+    }
+
+    fn set_value(&mut self, value: f32) {
+        let new_value = self.map_value(value);
 
         match self.smooth.as_mut() {
             Some(smooth) => smooth.set(self.value, new_value),
@@ -98,99 +529,174 @@ impl InputEndpoint {
         }
     }
 
+    fn receive(&mut self, value: f32) {
+        use InputEndpointKind::*;
+
+        if self.kind == Latch && value == self.received {
+            return self.process_value();
+        }
+        self.received = value;
+
+        match self.kind {
+            Follow | Latch => self.set_value(value),
+            Trigger | Pulse(_) | PulseMillis(_) => {
+                self.value = self.map_value(value);
+                self.held = self.pulse - 1;
+            }
+            Gate if value == 0.0 => {
+                self.open = false;
+                self.retrigger = false;
+                self.value = 0.0;
+            }
+            Gate if self.open => {
+                self.retrigger = true;
+                self.value = 0.0;
+            }
+            Gate => {
+                self.open = true;
+                self.value = self.map_value(value);
+            }
+            Toggle => {
+                self.open = !self.open;
+                self.value = match self.open {
+                    true => self.map_value(value),
+                    false => 0.0,
+                };
+            }
+            SampleAndHold => {}
+        }
+    }
+
     fn process_value(&mut self) {
+        use InputEndpointKind::*;
+
         match self.kind {
-            InputEndpointKind::Trigger => self.value = 0.0,
-            InputEndpointKind::Follow => {
+            Follow | Latch => {
                 if let Some(smooth) = self.smooth.as_mut() {
                     (*smooth).process(&mut self.value);
                 }
             }
+            Trigger | Pulse(_) | PulseMillis(_) => match self.held {
+                0 => self.value = 0.0,
+                _ => self.held -= 1,
+            },
+            Gate if self.retrigger => {
+                self.retrigger = false;
+                self.value = self.map_value(self.received);
+            }
+            Gate | Toggle | SampleAndHold => {}
+        }
+    }
+
+    /// Output the last value received when
+    /// the trigger rises above zero.
+    fn sample_on_trigger(&mut self) {
+        let triggered = self.trigger > 0.0;
+        if triggered && !self.triggered {
+            self.value = self.map_value(self.received);
         }
+        self.triggered = triggered;
     }
 }
 
-/// Mainly for internal use to wrap
-/// the creation of static data used
-/// for endpoints. That data container
-/// is then split into a pair:
-/// `(producer, consumer)`
+/// Create an input endpoint producer and consumer
+/// sharing a queue of their own.
 ///
 /// ```
-///     use rume_core::{endpoint, InputStream, OutputStream};
+///     use rume_core::{
+///         Output, InputEndpoint, InputEndpointOutput, make_input_endpoint, Processor
+///     };
 ///
 ///     const VALUE_TO_PASS: f32 = 3.14;
 ///
-///     let (in_producer, in_consumer) = endpoint!(InputStream);
-///     let (out_producer, out_consumer) = endpoint!(OutputStream);
-///
-///     in_producer.enqueue(VALUE_TO_PASS).unwrap();
-///     let value = in_consumer.dequeue().unwrap();
-///     out_producer.enqueue(value).unwrap();
+///     let (mut producer, consumer) = make_input_endpoint();
+///     let mut processor = InputEndpoint::new(consumer);
 ///
-///     assert_eq!(out_consumer.dequeue().unwrap(), VALUE_TO_PASS);
+///     producer.enqueue(VALUE_TO_PASS).unwrap();
+///     processor.process();
 ///
+///     assert_eq!(InputEndpointOutput.get(&mut processor), VALUE_TO_PASS);
 /// ```
-macro_rules! endpoint {
-    ($endpoint_type: ty) => {{
-        use $crate::heapless::{i, spsc};
-        static mut ENDPOINT: $endpoint_type = spsc::Queue(i::Queue::new());
-        unsafe { ENDPOINT.split() }
-    }};
+pub fn make_input_endpoint() -> (InputStreamProducer, InputStreamConsumer) {
+    make_input_endpoint_with(INPUT_STREAM_CAPACITY)
 }
 
-/// Create an input endpoint producer and consumer.
+/// Create an input endpoint producer and consumer
+/// streaming up to `capacity` values of type `T`.
 ///
 /// ```
-///     use rume_core::{
-///         Output, InputEndpoint, InputEndpointOutput, make_processor, make_input_endpoint, Processor
-///     };
+///     use rume_core::*;
 ///
-///     const VALUE_TO_PASS: f32 = 3.14;
+///     let (mut producer, consumer) = make_input_endpoint_with::<f64>(16);
+///     let mut processor = InputEndpoint::new(consumer);
 ///
-///     let (mut producer, consumer) = make_input_endpoint();
-///     let processor = make_processor(InputEndpoint::new(consumer));
+///     producer.enqueue(0.25).unwrap();
+///     processor.process();
 ///
-///     producer.enqueue(VALUE_TO_PASS).unwrap();
-///     processor.borrow_mut().process();
-///
-///     assert_eq!(InputEndpointOutput.get(processor.clone()), VALUE_TO_PASS);
+///     assert_eq!(producer.capacity(), 16);
+///     assert_eq!(InputEndpointOutput.get(&mut processor), 0.25);
 /// ```
-pub fn make_input_endpoint() -> (InputStreamProducer, InputStreamConsumer) {
-    endpoint!(InputStream)
+pub fn make_input_endpoint_with<T: Sample>(
+    capacity: usize,
+) -> (InputStreamProducer<T>, InputStreamConsumer<T>) {
+    stream(capacity)
 }
 
-/// Create an output endpoint producer and consumer.
+/// Create an output endpoint producer and consumer
+/// sharing a queue of their own.
 ///
 /// ```
 ///     use rume_core::{
-///         Input, OutputEndpoint, OutputEndpointInput, make_processor, make_output_endpoint
+///         Input, OutputEndpoint, OutputEndpointInput, Processor, make_output_endpoint
 ///     };
 ///
 ///     const VALUE_TO_PASS: f32 = 3.14;
 ///
 ///     let (producer, mut consumer) = make_output_endpoint();
-///     let processor = make_processor(OutputEndpoint::new(producer));
+///     let mut processor = OutputEndpoint::new(producer);
 ///
-///     OutputEndpointInput.set(processor.clone(), VALUE_TO_PASS);
+///     OutputEndpointInput.set(&mut processor, VALUE_TO_PASS);
+///     processor.process();
 ///     assert_eq!(consumer.dequeue().unwrap(), VALUE_TO_PASS);
 /// ```
 pub fn make_output_endpoint() -> (OutputStreamProducer, OutputStreamConsumer) {
-    endpoint!(OutputStream)
+    make_output_endpoint_with(OUTPUT_STREAM_CAPACITY)
+}
+
+/// Create an output endpoint producer and consumer
+/// streaming up to `capacity` values of type `T`.
+///
+/// ```
+///     use rume_core::*;
+///
+///     let (producer, mut consumer) = make_output_endpoint_with::<i16>(8192);
+///     let mut processor = OutputEndpoint::new(producer);
+///
+///     OutputEndpointInput.set(&mut processor, 0.5);
+///     processor.process();
+///
+///     assert_eq!(consumer.capacity(), 8192);
+///     assert_eq!(consumer.dequeue(), Some(i16::MAX / 2));
+/// ```
+pub fn make_output_endpoint_with<T: Sample>(
+    capacity: usize,
+) -> (OutputStreamProducer<T>, OutputStreamConsumer<T>) {
+    stream(capacity)
 }
 
-pub struct InputEndpointBuilder {
-    inner: InputEndpoint,
+pub struct InputEndpointBuilder<T: Sample = StreamDataType> {
+    inner: InputEndpoint<T>,
 }
 
-impl InputEndpointBuilder {
-    pub fn new(stream: InputStreamConsumer) -> Self {
+impl<T: Sample> InputEndpointBuilder<T> {
+    pub fn new(stream: InputStreamConsumer<T>) -> Self {
         Self {
             inner: InputEndpoint::new(stream),
         }
     }
 
     pub fn init(mut self, value: f32) -> Self {
+        self.inner.received = value;
         self.inner.value = value;
         self
     }
@@ -205,12 +711,51 @@ impl InputEndpointBuilder {
         self
     }
 
+    /// Also take values from `events`, each on
+    /// the sample of `clock` it is timed for.
+    pub fn events(mut self, events: EventConsumer, clock: Clock) -> Self {
+        self.inner.events = Some(EventReader::new(events, clock));
+        self
+    }
+
+    /// Treat incoming values, as well as the
+    /// `init` value, as normalised `0..1`
+    /// positions mapped into the range
+    /// (`0..1` if none) along `taper`.
+    pub fn taper(mut self, taper: Taper) -> Self {
+        self.inner.taper = Some(taper);
+        self
+    }
+
+    /// Smooth changes over a number of samples.
     pub fn smooth(mut self, smoothing: u32) -> Self {
-        self.inner.smooth = Some(ValueSmoother::new(smoothing));
+        let curve = self.smoothing_curve();
+        self.inner.smooth = Some(ValueSmoother::new(smoothing).with_curve(curve));
+        self
+    }
+
+    /// Smooth changes over a number of
+    /// milliseconds, whatever the rate.
+    pub fn smooth_millis(mut self, ms: f32) -> Self {
+        let curve = self.smoothing_curve();
+        self.inner.smooth = Some(ValueSmoother::millis(ms).with_curve(curve));
+        self
+    }
+
+    pub fn smoothing(mut self, curve: SmoothingCurve) -> Self {
+        let smooth = self.inner.smooth.take().unwrap_or_default();
+        self.inner.smooth = Some(smooth.with_curve(curve));
         self
     }
 
-    pub fn build(self) -> InputEndpoint {
+    fn smoothing_curve(&self) -> SmoothingCurve {
+        self.inner
+            .smooth
+            .as_ref()
+            .map_or(SmoothingCurve::default(), |smooth| smooth.curve)
+    }
+
+    pub fn build(self) -> InputEndpoint<T> {
         self.inner
     }
 }
@@ -235,44 +780,210 @@ impl RangedData {
     }
 }
 
+/// The curve along which a tapered input
+/// endpoint maps normalised `0..1` values
+/// into its range.
+#[derive(Debug, Clone, Copy)]
+pub enum Taper {
+    /// Evenly from start to end.
+    Linear,
+    /// Equal steps give equal ratios, as
+    /// for frequencies. Both ends of the
+    /// range must be positive.
+    Log,
+    /// Evenly over a range in decibels,
+    /// output as a gain.
+    Decibels,
+    /// Linear, snapped to a number of
+    /// evenly spaced positions.
+    Stepped(u32),
+    /// Linear, rounded to whole numbers.
+    Integer,
+    /// Linear after a curve mapping
+    /// `0..1` onto `0..1`.
+    Custom(fn(f32) -> f32),
+}
+
+impl Taper {
+    /// Map a normalised value, clamped to
+    /// `0..1`, into `range`.
+    pub fn map(self, value: f32, range: &Range<f32>) -> f32 {
+        let position = value.clamp(0.0, 1.0);
+        let lerp = |position: f32| range.start + position * (range.end - range.start);
+        match self {
+            Taper::Linear => lerp(position),
+            Taper::Log => range.start * libm::powf(range.end / range.start, position),
+            Taper::Decibels => db::to_gain(lerp(position)),
+            Taper::Stepped(steps) => {
+                let last = steps.saturating_sub(1) as f32;
+                if last > 0.0 {
+                    lerp(libm::roundf(position * last) / last)
+                } else {
+                    range.start
+                }
+            }
+            Taper::Integer => libm::roundf(lerp(position)),
+            Taper::Custom(curve) => lerp(curve(position).clamp(0.0, 1.0)),
+        }
+    }
+}
+
+/// The curve along which a smoother
+/// moves towards a new value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SmoothingCurve {
+    /// At a constant rate.
+    #[default]
+    Linear,
+    /// Quickly at first, then slowing down,
+    /// within 60 dB of the target in time.
+    OnePole,
+    /// Easing in and out along half a cosine.
+    /// A new target arriving mid-ramp keeps
+    /// the current slope.
+    RaisedCosine,
+}
+
+/// Moves a value towards its latest target
+/// through a number of samples, or a time
+/// converted to samples at `prepare`, and
+/// lands on it the sample after. A target
+/// arriving mid-ramp starts a new ramp from
+/// the current value.
 #[derive(Debug)]
 pub struct ValueSmoother {
+    curve: SmoothingCurve,
+    millis: Option<f32>,
+    start: f32,
     target: f32,
-    increment: f32,
+    slope: f32,
+    velocity: f32,
+    coefficient: f32,
     steps: u32,
     step: u32,
 }
 
+impl Default for ValueSmoother {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
 impl ValueSmoother {
     pub fn new(smoothing: u32) -> Self {
-        Self {
+        let mut smoother = Self {
+            curve: SmoothingCurve::Linear,
+            millis: None,
+            start: 0.0,
             target: 0.0,
-            increment: 0.0,
-            steps: smoothing,
+            slope: 0.0,
+            velocity: 0.0,
+            coefficient: 1.0,
+            steps: 0,
             step: 0,
+        };
+        smoother.set_steps(smoothing);
+        smoother
+    }
+
+    /// A smoother taking `ms` milliseconds
+    /// at the rate given to `prepare`.
+    pub fn millis(ms: f32) -> Self {
+        Self {
+            millis: Some(ms),
+            ..Self::new(0)
         }
     }
 
+    pub fn with_curve(mut self, curve: SmoothingCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn prepare(&mut self, sample_rate: usize) {
+        if let Some(ms) = self.millis {
+            let steps = tick::from_millis(ms, sample_rate as f32);
+            self.set_steps(libm::roundf(steps.max(0.0)) as u32);
+        }
+    }
+
+    fn set_steps(&mut self, steps: u32) {
+        self.steps = steps;
+        self.step = steps;
+        self.coefficient = match steps {
+            0 => 1.0,
+            _ => 1.0 - libm::powf(0.001, 1.0 / (steps + 1) as f32),
+        };
+    }
+
     fn set(&mut self, current: f32, target: f32) {
+        let ramping = self.step < self.steps;
+        self.slope = match (self.curve, ramping) {
+            (SmoothingCurve::RaisedCosine, true) => self.velocity,
+            _ => 0.0,
+        };
+        self.start = current;
         self.target = target;
-        self.increment = (self.target - current) / self.steps as f32;
         self.step = 0;
     }
 
     fn process(&mut self, value: &mut f32) {
         if self.step < self.steps {
-            *value += self.increment;
             self.step += 1;
+            let position = self.step as f32 / (self.steps + 1) as f32;
+            let distance = self.target - self.start;
+            let next = match self.curve {
+                SmoothingCurve::Linear => self.start + distance * position,
+                SmoothingCurve::OnePole => *value + (self.target - *value) * self.coefficient,
+                SmoothingCurve::RaisedCosine => {
+                    let eased = 0.5 - 0.5 * libm::cosf(core::f32::consts::PI * position);
+                    let length = (self.steps + 1) as f32;
+                    let kept = self.slope * length * position * (1.0 - position) * (1.0 - position);
+                    self.start + distance * eased + kept
+                }
+            };
+            self.velocity = next - *value;
+            *value = next;
         } else {
+            self.velocity = 0.0;
             *value = self.target;
         }
     }
 }
 
-#[derive(Debug)]
+/// How an input endpoint turns the values
+/// it receives into its output. `Follow`
+/// and `Latch` are smoothed, the others
+/// change on the sample a value arrives.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputEndpointKind {
+    /// Outputs each value.
     Follow,
+    /// Outputs each value for a single
+    /// sample, then falls back to zero.
     Trigger,
+    /// Outputs each value for a number of
+    /// samples, then falls back to zero.
+    Pulse(u32),
+    /// Outputs each value for a number of
+    /// milliseconds, converted to samples
+    /// at `prepare`.
+    PulseMillis(f32),
+    /// Opens on a value and holds it until
+    /// a zero releases it. A value arriving
+    /// while open closes the gate for one
+    /// sample, so envelopes retrigger.
+    Gate,
+    /// Each value flips the output between
+    /// that value and zero.
+    Toggle,
+    /// Outputs each value, ignoring values
+    /// repeating the last one received.
+    Latch,
+    /// Holds its output, taking the last
+    /// value received each time its `input`
+    /// trigger rises above zero.
+    SampleAndHold,
 }
 
 #[cfg(test)]
@@ -284,10 +995,9 @@ mod test {
         const INIT_VALUE: f32 = 3.14;
 
         let (_, consumer) = make_input_endpoint();
-        let endpoint = InputEndpointBuilder::new(consumer).init(INIT_VALUE).build();
-        let processor = make_processor(endpoint);
+        let mut processor = InputEndpointBuilder::new(consumer).init(INIT_VALUE).build();
 
-        assert_eq!(InputEndpointOutput.get(processor.clone()), INIT_VALUE);
+        assert_eq!(InputEndpointOutput.get(&mut processor), INIT_VALUE);
     }
 
     #[test]
@@ -299,16 +1009,14 @@ mod test {
         const VALUE_BELLOW_RANGE: f32 = MIN_VALUE - 10.0;
 
         let (mut producer, consumer) = make_input_endpoint();
-        let processor = make_processor(
-            InputEndpointBuilder::new(consumer)
-                .range(MIN_VALUE..MAX_VALUE)
-                .build(),
-        );
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .range(MIN_VALUE..MAX_VALUE)
+            .build();
 
         let mut test_value_passing = |value_to_pass: f32, expected: f32| {
             producer.enqueue(value_to_pass).unwrap();
-            processor.borrow_mut().process();
-            assert_eq!(InputEndpointOutput.get(processor.clone()), expected);
+            processor.process();
+            assert_eq!(InputEndpointOutput.get(&mut processor), expected);
         };
 
         test_value_passing(VALUE_IN_RANGE, VALUE_IN_RANGE);
@@ -316,6 +1024,61 @@ mod test {
         test_value_passing(VALUE_BELLOW_RANGE, MIN_VALUE);
     }
 
+    #[test]
+    fn tapers_map_normalised_values_into_range() {
+        fn assert_near(actual: f32, expected: f32) {
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+
+        let frequencies = 20.0..20000.0;
+        assert_near(Taper::Linear.map(0.5, &frequencies), 10010.0);
+        assert_near(Taper::Log.map(0.0, &frequencies), 20.0);
+        assert_near(Taper::Log.map(0.5, &frequencies), 632.456);
+        assert_near(Taper::Log.map(1.0, &frequencies), 20000.0);
+        assert_near(Taper::Log.map(2.0, &frequencies), 20000.0);
+
+        let decibels = -100.0..0.0;
+        assert_near(Taper::Decibels.map(0.0, &decibels), 0.0);
+        assert_near(Taper::Decibels.map(0.8, &decibels), 0.1);
+        assert_near(Taper::Decibels.map(1.0, &decibels), 1.0);
+
+        let steps = 0.0..1.0;
+        assert_near(Taper::Stepped(3).map(0.2, &steps), 0.0);
+        assert_near(Taper::Stepped(3).map(0.3, &steps), 0.5);
+        assert_near(Taper::Stepped(3).map(0.9, &steps), 1.0);
+        assert_near(Taper::Stepped(1).map(0.9, &steps), 0.0);
+        assert_near(Taper::Integer.map(0.5, &(0.0..7.0)), 4.0);
+        assert_near(Taper::Custom(|x| x * x).map(0.5, &(0.0..8.0)), 2.0);
+    }
+
+    #[test]
+    fn input_endpoint_with_taper_maps_values_and_init() {
+        let (mut producer, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .range(20.0..20000.0)
+            .taper(Taper::Log)
+            .init(1.0)
+            .smooth(4)
+            .build();
+
+        processor.prepare(AudioConfig::default());
+        assert_eq!(InputEndpointOutput.get(&mut processor), 20000.0);
+
+        processor.prepare(AudioConfig::default());
+        assert_eq!(InputEndpointOutput.get(&mut processor), 20000.0);
+
+        producer.enqueue(0.0).unwrap();
+        for _ in 0..6 {
+            processor.process();
+        }
+        assert_eq!(InputEndpointOutput.get(&mut processor), 20.0);
+    }
+
     #[test]
     fn input_endpoint_with_smoothing_is_smooth() {
         const INIT_VALUE: f32 = -53.1;
@@ -328,27 +1091,117 @@ mod test {
         assert!(INIT_VALUE.abs() < TARGET_VALUE.abs());
 
         let (mut producer, consumer) = make_input_endpoint();
-        let processor = make_processor(
-            InputEndpointBuilder::new(consumer)
-                .init(INIT_VALUE)
-                .smooth(SMOOTHING)
-                .build(),
-        );
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .init(INIT_VALUE)
+            .smooth(SMOOTHING)
+            .build();
 
         producer.enqueue(TARGET_VALUE).unwrap();
 
-        processor.borrow_mut().process();
-        assert_eq!(InputEndpointOutput.get(processor.clone()), INIT_VALUE);
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), INIT_VALUE);
 
         for _ in 0..SMOOTHING {
-            processor.borrow_mut().process();
-            let value = InputEndpointOutput.get(processor.clone());
+            processor.process();
+            let value = InputEndpointOutput.get(&mut processor);
             assert!(value.abs() < TARGET_VALUE.abs());
             assert!(value.abs() > INIT_VALUE.abs());
         }
 
-        processor.borrow_mut().process();
-        assert_eq!(InputEndpointOutput.get(processor.clone()), TARGET_VALUE);
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), TARGET_VALUE);
+    }
+
+    fn ramp(smoother: &mut ValueSmoother, value: &mut f32, samples: usize) -> Vec<f32> {
+        (0..samples)
+            .map(|_| {
+                smoother.process(value);
+                *value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn smoothing_in_millis_follows_the_sample_rate() {
+        let mut smoother = ValueSmoother::millis(1.0);
+        let mut value = 0.0;
+
+        smoother.prepare(48_000);
+        smoother.set(value, 1.0);
+        let values = ramp(&mut smoother, &mut value, 49);
+        assert!(values[47] < 1.0);
+        assert_eq!(values[48], 1.0);
+
+        smoother.prepare(8_000);
+        smoother.set(value, 0.0);
+        let values = ramp(&mut smoother, &mut value, 9);
+        assert!(values[7] > 0.0);
+        assert_eq!(values[8], 0.0);
+    }
+
+    #[test]
+    fn smoothing_curves_have_their_shape() {
+        const STEPS: u32 = 99;
+
+        let mut first_steps = [0.0; 3];
+        let curves = [
+            SmoothingCurve::Linear,
+            SmoothingCurve::OnePole,
+            SmoothingCurve::RaisedCosine,
+        ];
+        for (curve, first_step) in curves.iter().zip(first_steps.iter_mut()) {
+            let mut smoother = ValueSmoother::new(STEPS).with_curve(*curve);
+            let mut value = 0.0;
+            smoother.set(value, 1.0);
+
+            let values = ramp(&mut smoother, &mut value, STEPS as usize + 1);
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(values[STEPS as usize - 1] > 0.98);
+            assert_eq!(values[STEPS as usize], 1.0);
+            *first_step = values[0];
+        }
+
+        let [linear, one_pole, cosine] = first_steps;
+        assert!((linear - 0.01).abs() < 1e-6);
+        assert!(one_pole > linear);
+        assert!(cosine < linear);
+    }
+
+    #[test]
+    fn raised_cosine_retargets_smoothly() {
+        let mut smoother = ValueSmoother::new(99).with_curve(SmoothingCurve::RaisedCosine);
+        let mut value = 0.0;
+
+        smoother.set(value, 1.0);
+        let before = ramp(&mut smoother, &mut value, 30);
+        smoother.set(value, 0.5);
+        let after = ramp(&mut smoother, &mut value, 2);
+
+        let slope_before = before[29] - before[28];
+        let slope_after = after[0] - before[29];
+        assert!((slope_after - slope_before).abs() < 1e-3);
+        assert!(after[1] > after[0]);
+    }
+
+    #[test]
+    fn input_endpoint_smooths_in_millis_once_prepared() {
+        let (mut producer, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .smoothing(SmoothingCurve::OnePole)
+            .smooth_millis(2.0)
+            .build();
+
+        processor.prepare(1000.into());
+        producer.enqueue(1.0).unwrap();
+
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), 0.0);
+        for _ in 0..2 {
+            processor.process();
+            assert!(InputEndpointOutput.get(&mut processor) < 1.0);
+        }
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), 1.0);
     }
 
     #[test]
@@ -356,18 +1209,264 @@ mod test {
         const TRIGGER_VALUE: f32 = 1000.0;
 
         let (mut producer, consumer) = make_input_endpoint();
-        let processor = make_processor(
-            InputEndpointBuilder::new(consumer)
-                .kind(InputEndpointKind::Trigger)
-                .build(),
-        );
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .kind(InputEndpointKind::Trigger)
+            .build();
 
         producer.enqueue(TRIGGER_VALUE).unwrap();
 
-        processor.borrow_mut().process();
-        assert_eq!(InputEndpointOutput.get(processor.clone()), TRIGGER_VALUE);
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), TRIGGER_VALUE);
+
+        processor.process();
+        assert_eq!(InputEndpointOutput.get(&mut processor), 0.0);
+    }
+
+    fn outputs_of(kind: InputEndpointKind, messages: &[Option<f32>]) -> Vec<f32> {
+        let (mut producer, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer).kind(kind).build();
+        processor.prepare(1000.into());
+
+        messages
+            .iter()
+            .map(|message| {
+                if let Some(value) = message {
+                    producer.enqueue(*value).unwrap();
+                }
+                processor.process();
+                InputEndpointOutput.get(&mut processor)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn input_endpoint_as_pulse_holds_for_its_length() {
+        let messages = [Some(2.0), None, None, None, Some(3.0), None];
+
+        let pulse = outputs_of(InputEndpointKind::Pulse(3), &messages);
+        assert_eq!(pulse, [2.0, 2.0, 2.0, 0.0, 3.0, 3.0]);
+
+        let pulse = outputs_of(InputEndpointKind::PulseMillis(2.0), &messages);
+        assert_eq!(pulse, [2.0, 2.0, 0.0, 0.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn input_endpoint_as_gate_holds_until_released() {
+        let messages = [Some(1.0), None, Some(0.5), None, None, Some(0.0), None];
+
+        let gate = outputs_of(InputEndpointKind::Gate, &messages);
+        assert_eq!(gate, [1.0, 1.0, 0.0, 0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn input_endpoint_as_toggle_flips_on_each_value() {
+        let messages = [Some(1.0), None, Some(1.0), None, Some(0.7)];
+
+        let toggle = outputs_of(InputEndpointKind::Toggle, &messages);
+        assert_eq!(toggle, [1.0, 1.0, 0.0, 0.0, 0.7]);
+    }
+
+    #[test]
+    fn input_endpoint_as_sample_and_hold_changes_on_its_trigger() {
+        let (mut producer, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .kind(InputEndpointKind::SampleAndHold)
+            .build();
+        processor.prepare(1000.into());
+
+        let mut values = Vec::new();
+        let messages = [Some(1.0), None, Some(2.0), None, Some(3.0), None];
+        for (message, trigger) in messages.iter().zip([0.0, 1.0, 1.0, 0.0, 0.0, 1.0]) {
+            if let Some(value) = message {
+                producer.enqueue(*value).unwrap();
+            }
+            InputEndpointTrigger.set(&mut processor, trigger);
+            processor.process();
+            values.push(InputEndpointOutput.get(&mut processor));
+        }
+
+        assert_eq!(values, [0.0, 1.0, 1.0, 1.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn input_endpoint_receives_its_signal_when_it_changes() {
+        let (_, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .kind(InputEndpointKind::Trigger)
+            .init(0.5)
+            .build();
+        processor.prepare(1000.into());
+
+        let mut values = Vec::new();
+        for signal in [0.5, 1.0, 1.0, 1.0, 0.0, 0.7].iter() {
+            InputEndpointSignal.set(&mut processor, *signal);
+            processor.process();
+            values.push(InputEndpointOutput.get(&mut processor));
+        }
+
+        assert_eq!(values, [0.0, 1.0, 0.0, 0.0, 0.0, 0.7]);
+    }
+
+    #[test]
+    fn momentary_kinds_do_not_fire_again_when_prepared() {
+        let kinds = [
+            InputEndpointKind::Trigger,
+            InputEndpointKind::Pulse(4),
+            InputEndpointKind::Gate,
+            InputEndpointKind::Toggle,
+        ];
+        for kind in kinds.iter() {
+            let (mut producer, consumer) = make_input_endpoint();
+            let mut processor = InputEndpointBuilder::new(consumer).kind(*kind).build();
+            processor.prepare(1000.into());
+
+            producer.enqueue(1.0).unwrap();
+            processor.process();
+            assert_eq!(InputEndpointOutput.get(&mut processor), 1.0);
+
+            processor.prepare(1000.into());
+            assert_eq!(InputEndpointOutput.get(&mut processor), 0.0, "{:?}", kind);
+            processor.process();
+            assert_eq!(InputEndpointOutput.get(&mut processor), 0.0, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn input_endpoint_as_latch_ignores_repeated_values() {
+        let (mut producer, consumer) = make_input_endpoint();
+        let mut processor = InputEndpointBuilder::new(consumer)
+            .kind(InputEndpointKind::Latch)
+            .smooth(3)
+            .build();
+
+        let mut values = Vec::new();
+        for message in [Some(4.0), None, Some(4.0), None, None, None].iter() {
+            if let Some(value) = message {
+                producer.enqueue(*value).unwrap();
+            }
+            processor.process();
+            values.push(InputEndpointOutput.get(&mut processor));
+        }
+
+        assert_eq!(values, [0.0, 1.0, 2.0, 3.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn input_endpoints_do_not_share_a_queue() {
+        let (mut freq_producer, freq_consumer) = make_input_endpoint();
+        let (mut amp_producer, amp_consumer) = make_input_endpoint();
+        let mut freq = InputEndpoint::new(freq_consumer);
+        let mut amp = InputEndpoint::new(amp_consumer);
+
+        freq_producer.enqueue(440.0).unwrap();
+        amp_producer.enqueue(0.5).unwrap();
+        freq.process();
+        amp.process();
+
+        assert_eq!(InputEndpointOutput.get(&mut freq), 440.0);
+        assert_eq!(InputEndpointOutput.get(&mut amp), 0.5);
+    }
+
+    #[test]
+    fn output_endpoints_do_not_share_a_queue() {
+        let (left_producer, mut left_consumer) = make_output_endpoint();
+        let (right_producer, mut right_consumer) = make_output_endpoint();
+        let mut left = OutputEndpoint::new(left_producer);
+        let mut right = OutputEndpoint::new(right_producer);
+
+        OutputEndpointInput.set(&mut left, 1.0);
+        OutputEndpointInput.set(&mut right, -1.0);
+        left.process();
+        right.process();
+
+        assert_eq!(left_consumer.dequeue(), Some(1.0));
+        assert_eq!(left_consumer.dequeue(), None);
+        assert_eq!(right_consumer.dequeue(), Some(-1.0));
+        assert_eq!(right_consumer.dequeue(), None);
+    }
+
+    #[test]
+    fn endpoints_can_be_sized_and_typed() {
+        let (mut producer, consumer) = make_input_endpoint_with::<f64>(4);
+        let mut input = InputEndpoint::new(consumer);
+        assert_eq!(producer.capacity(), 4);
+
+        producer.enqueue(2.5).unwrap();
+        input.process();
+        assert_eq!(InputEndpointOutput.get(&mut input), 2.5);
+
+        let (producer, mut consumer) = make_output_endpoint_with::<i16>(2);
+        let mut output = OutputEndpoint::new(producer);
+        assert_eq!(consumer.capacity(), 2);
+
+        OutputEndpointInput.set(&mut output, -1.0);
+        output.process();
+        OutputEndpointInput.set(&mut output, 2.0);
+        output.process();
+        assert_eq!(consumer.dequeue(), Some(-i16::MAX));
+        assert_eq!(consumer.dequeue(), Some(i16::MAX));
+    }
+
+    #[test]
+    fn integer_samples_span_the_unit_range() {
+        assert_eq!(i32::from_f32(1.0), i32::MAX);
+        assert_eq!(i32::from_f32(0.0), 0);
+        assert_eq!(i16::from_f32(-0.5), -i16::MAX / 2);
+        assert_eq!(i16::MAX.to_f32(), 1.0);
+        assert_eq!(0_i16.to_f32(), 0.0);
+    }
+
+    #[test]
+    fn full_output_endpoint_counts_overflows() {
+        let (producer, mut consumer) = make_output_endpoint_with::<f32>(2);
+        let mut processor = OutputEndpoint::new(producer.with_overflow(Overflow::DropOldest));
+
+        for value in 0..4 {
+            OutputEndpointInput.set(&mut processor, value as f32);
+            processor.process();
+        }
+
+        assert_eq!(processor.overflows(), 2);
+        assert_eq!(consumer.overflows(), 2);
+        assert_eq!(consumer.pop(), 2.0);
+        assert_eq!(consumer.pop(), 3.0);
+        assert_eq!(consumer.pop(), 0.0);
+        assert_eq!(processor.underruns(), 1);
+    }
+
+    #[test]
+    fn output_endpoint_channels_are_interleaved() {
+        let (producer, mut consumer) = make_output_endpoint();
+        let mut processor = OutputEndpoint::new(producer).with_channels(2);
+        assert_eq!(consumer.channels(), 2);
+
+        for frame in 1..=2 {
+            OutputEndpointInput.set(&mut processor, frame as f32);
+            OutputEndpointChannel::<1>.set(&mut processor, -frame as f32);
+            processor.process();
+        }
+
+        let mut buffer = [0.0; 4];
+        assert_eq!(consumer.read_interleaved(&mut buffer), 2);
+        assert_eq!(buffer, [1.0, -1.0, 2.0, -2.0]);
+    }
+
+    #[test]
+    fn output_endpoint_can_follow_the_config_channels() {
+        let (producer, mut consumer) = make_output_endpoint();
+        let mut processor = OutputEndpoint::new(producer).with_config_channels();
+
+        processor.prepare(AudioConfig {
+            num_channels: 4,
+            ..Default::default()
+        });
+        OutputEndpointChannel::<3>.set(&mut processor, 1.0);
+        processor.process();
 
-        processor.borrow_mut().process();
-        assert_eq!(InputEndpointOutput.get(processor.clone()), 0.0);
+        let mut channels = [[0.0; 1]; 4];
+        let [a, b, c, d] = &mut channels;
+        assert_eq!(consumer.channels(), 4);
+        assert_eq!(consumer.read_planar(&mut [a, b, c, d]), 1);
+        assert_eq!(channels, [[0.0], [0.0], [0.0], [1.0]]);
     }
 }
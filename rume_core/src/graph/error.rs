@@ -0,0 +1,91 @@
+use crate::lib::*;
+use core::fmt;
+
+/// The reasons a `SignalChain` can fail
+/// to build or to be edited. Processors
+/// are referred to by the order in which
+/// they were added to the `SignalChainBuilder`
+/// or `SignalChainEditor`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    /// A connection refers to a processor
+    /// that was not added to the chain.
+    UnknownProcessor,
+    /// The same output is connected to
+    /// the same input more than once.
+    DuplicateConnection { output: usize, input: usize },
+    /// These processors form a loop that
+    /// is not closed by a feedback connection.
+    Cycle(Vec<usize>),
+    /// A processor input that must be
+    /// connected is left unconnected.
+    UnconnectedInput { processor: usize, port: usize },
+    /// A connection uses a port index the
+    /// processor does not declare.
+    PortOutOfRange { processor: usize, port: usize },
+    /// A connection uses a port that belongs
+    /// to another type of processor.
+    MismatchedPort { processor: usize, port: usize },
+    /// A subgraph or block processor declares more
+    /// input or output ports than a block can carry.
+    TooManyPorts(usize),
+    /// A connection to remove does not exist.
+    UnknownConnection { output: usize, input: usize },
+    /// The chain has not picked up the edits
+    /// committed before, so there is no room
+    /// for more until it renders again.
+    PendingEdits,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::UnknownProcessor => {
+                write!(
+                    f,
+                    "a connection refers to a processor that is not in the chain"
+                )
+            }
+            GraphError::DuplicateConnection { output, input } => write!(
+                f,
+                "processor {} is connected to processor {} more than once",
+                output, input
+            ),
+            GraphError::Cycle(processors) => write!(
+                f,
+                "processors {:?} form a cycle, close it with a feedback connection",
+                processors
+            ),
+            GraphError::UnconnectedInput { processor, port } => write!(
+                f,
+                "input {} of processor {} must be connected",
+                port, processor
+            ),
+            GraphError::PortOutOfRange { processor, port } => {
+                write!(f, "processor {} has no port {}", processor, port)
+            }
+            GraphError::MismatchedPort { processor, port } => write!(
+                f,
+                "port {} does not belong to the type of processor {}",
+                port, processor
+            ),
+            GraphError::TooManyPorts(num_ports) => write!(
+                f,
+                "a block processor cannot have {} ports, the most it can have is {}",
+                num_ports,
+                crate::max_block_ports()
+            ),
+            GraphError::UnknownConnection { output, input } => write!(
+                f,
+                "processor {} is not connected to processor {}",
+                output, input
+            ),
+            GraphError::PendingEdits => {
+                write!(f, "the chain has not picked up the previous edits yet")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GraphError {}
@@ -1,121 +1,232 @@
-use crate::{lib::*, proc::*};
+use crate::{lib::*, node::*, proc::*};
+use core::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+};
 
-pub type DynInputPort = InputPort<dyn Processor, dyn Input<dyn Processor>>;
-pub type DynOutputPort = OutputPort<dyn Processor, dyn Output<dyn Processor>>;
-pub type DynConnection =
-    Connection<dyn Processor, dyn Input<dyn Processor>, dyn Processor, dyn Output<dyn Processor>>;
-
-pub trait Input<P>
+pub trait Input<P>: Send + Sync
 where
-    P: Processor + ?Sized,
+    P: Processor,
 {
-    fn set(&self, proc: SharedProc<P>, data: f32);
+    fn set(&self, proc: &mut P, data: f32);
 }
 
-pub trait Output<P>
+pub trait Output<P>: Send + Sync
 where
-    P: Processor + ?Sized,
+    P: Processor,
 {
-    fn get(&self, proc: SharedProc<P>) -> f32;
+    fn get(&self, proc: &mut P) -> f32;
 }
 
-pub struct InputPort<P, I>
-where
-    P: Processor + ?Sized,
-    I: Input<P> + ?Sized,
-{
-    pub proc: SharedProc<P>,
-    pub port: Box<I>,
+/// How an input combines the data of
+/// several connections feeding it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Merge {
+    #[default]
+    Sum,
+    Max,
+    Multiply,
+    /// Keep the data of the connection
+    /// from the last processor to render.
+    Last,
 }
 
-impl<P, I> InputPort<P, I>
-where
-    P: Processor + ?Sized,
-    I: Input<P> + ?Sized,
-{
+impl Merge {
     #[inline(always)]
-    pub fn set(&mut self, data: f32) {
-        self.port.set(self.proc.clone(), data);
+    pub fn apply(&self, merged: f32, value: f32) -> f32 {
+        match self {
+            Merge::Sum => merged + value,
+            Merge::Max => merged.max(value),
+            Merge::Multiply => merged * value,
+            Merge::Last => value,
+        }
     }
 }
 
-impl<P, I> PartialEq for InputPort<P, I>
-where
-    P: Processor + ?Sized,
-    I: Input<P> + ?Sized,
-{
-    fn eq(&self, other: &InputPort<P, I>) -> bool {
-        Rc::ptr_eq(&self.proc, &other.proc)
+/// An `Input` bound to the type of the processor
+/// it belongs to, so that it can be set on any
+/// processor and only sets those of that type.
+trait AnyInput: Send + Sync {
+    fn set(&self, proc: &mut dyn Any, data: f32);
+}
+
+/// An `Output` bound to the type of the processor
+/// it belongs to, so that it can be read from any
+/// processor and only reads those of that type.
+trait AnyOutput: Send + Sync {
+    fn get(&self, proc: &mut dyn Any) -> Option<f32>;
+}
+
+struct Bound<P, T> {
+    port: T,
+    processor: PhantomData<fn(&mut P)>,
+}
+
+impl<P, T> Bound<P, T> {
+    fn new(port: T) -> Self {
+        Self {
+            port,
+            processor: PhantomData,
+        }
     }
 }
 
-pub struct OutputPort<P, O>
+impl<P, I> AnyInput for Bound<P, I>
 where
-    P: Processor + ?Sized,
-    O: Output<P> + ?Sized,
+    P: Processor + 'static,
+    I: Input<P>,
 {
-    pub proc: SharedProc<P>,
-    pub port: Box<O>,
+    fn set(&self, proc: &mut dyn Any, data: f32) {
+        if let Some(proc) = proc.downcast_mut::<P>() {
+            self.port.set(proc, data);
+        }
+    }
 }
 
-impl<P, O> OutputPort<P, O>
+impl<P, O> AnyOutput for Bound<P, O>
 where
-    P: Processor + ?Sized,
-    O: Output<P> + ?Sized,
+    P: Processor + 'static,
+    O: Output<P>,
 {
+    fn get(&self, proc: &mut dyn Any) -> Option<f32> {
+        proc.downcast_mut::<P>().map(|proc| self.port.get(proc))
+    }
+}
+
+/// An input of a processor in a `SignalChain`,
+/// identified by the processor's node and the
+/// index of the port on it.
+#[derive(Clone)]
+pub struct InputPort {
+    node: NodeId,
+    processor: TypeId,
+    port: Arc<dyn AnyInput>,
+    index: usize,
+}
+
+impl InputPort {
+    /// The input `index` of `processor`. The port
+    /// type is that of the field `port` points at,
+    /// which is never read, and must be an `Input`
+    /// of the processor's type.
+    pub fn of<P, I>(processor: Handle<P>, index: usize, _port: fn(&P) -> &I) -> Self
+    where
+        P: Processor + 'static,
+        I: Input<P> + Default + 'static,
+    {
+        Self {
+            node: processor.id(),
+            processor: TypeId::of::<P>(),
+            port: Arc::new(Bound::<P, I>::new(I::default())),
+            index,
+        }
+    }
+
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The type of the processor
+    /// this port belongs to.
+    pub fn processor(&self) -> TypeId {
+        self.processor
+    }
+
+    /// Set the input on `proc`, if it is
+    /// the processor type the port is for.
     #[inline(always)]
-    pub fn get(&mut self) -> f32 {
-        self.port.get(self.proc.clone())
+    pub fn set(&self, proc: &mut dyn Any, data: f32) {
+        self.port.set(proc, data);
     }
 }
 
-impl<P, O> PartialEq for OutputPort<P, O>
-where
-    P: Processor + ?Sized,
-    O: Output<P> + ?Sized,
-{
-    fn eq(&self, other: &OutputPort<P, O>) -> bool {
-        Rc::ptr_eq(&self.proc, &other.proc)
+impl PartialEq for InputPort {
+    fn eq(&self, other: &InputPort) -> bool {
+        self.node == other.node && self.index == other.index
     }
 }
 
-pub struct Connection<PIn, I, POut, O>
-where
-    PIn: Processor + ?Sized,
-    I: Input<PIn> + ?Sized,
-    POut: Processor + ?Sized,
-    O: Output<POut> + ?Sized,
-{
-    pub input: InputPort<PIn, I>,
-    pub output: OutputPort<POut, O>,
+/// An output of a processor in a `SignalChain`,
+/// identified by the processor's node and the
+/// index of the port on it.
+#[derive(Clone)]
+pub struct OutputPort {
+    node: NodeId,
+    processor: TypeId,
+    port: Arc<dyn AnyOutput>,
+    index: usize,
 }
 
-impl<PIn, I, POut, O> Connection<PIn, I, POut, O>
-where
-    PIn: Processor + ?Sized,
-    I: Input<PIn> + ?Sized,
-    POut: Processor + ?Sized,
-    O: Output<POut> + ?Sized,
-{
-    pub fn new(output: OutputPort<POut, O>, input: InputPort<PIn, I>) -> Self {
-        Self { input, output }
+impl OutputPort {
+    /// The output `index` of `processor`. The port
+    /// type is that of the field `port` points at,
+    /// which is never read, and must be an `Output`
+    /// of the processor's type.
+    pub fn of<P, O>(processor: Handle<P>, index: usize, _port: fn(&P) -> &O) -> Self
+    where
+        P: Processor + 'static,
+        O: Output<P> + Default + 'static,
+    {
+        Self {
+            node: processor.id(),
+            processor: TypeId::of::<P>(),
+            port: Arc::new(Bound::<P, O>::new(O::default())),
+            index,
+        }
     }
 
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The type of the processor
+    /// this port belongs to.
+    pub fn processor(&self) -> TypeId {
+        self.processor
+    }
+
+    /// Read the output of `proc`, or silence
+    /// if it is not the processor type the
+    /// port is for.
     #[inline(always)]
-    pub fn transfer(&mut self) {
-        self.input.set(self.output.get());
+    pub fn get(&self, proc: &mut dyn Any) -> f32 {
+        self.port.get(proc).unwrap_or_default()
     }
 }
 
-impl<PIn, I, POut, O> PartialEq for Connection<PIn, I, POut, O>
-where
-    PIn: Processor + ?Sized,
-    I: Input<PIn> + ?Sized,
-    POut: Processor + ?Sized,
-    O: Output<POut> + ?Sized,
-{
-    fn eq(&self, other: &Connection<PIn, I, POut, O>) -> bool {
-        self.input == other.input && self.output == other.output
+impl PartialEq for OutputPort {
+    fn eq(&self, other: &OutputPort) -> bool {
+        self.node == other.node && self.index == other.index
+    }
+}
+
+/// A processor whose ports can be looked up by
+/// index at runtime, as when a chain is built
+/// from a description rather than from code.
+/// `#[processor]` implements it for the ports
+/// it declares.
+pub trait IndexedPorts: Processor + Sized {
+    fn input_port(processor: Handle<Self>, index: usize) -> Option<InputPort>;
+    fn output_port(processor: Handle<Self>, index: usize) -> Option<OutputPort>;
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Connection {
+    pub input: InputPort,
+    pub output: OutputPort,
+}
+
+impl Connection {
+    pub fn new(output: OutputPort, input: InputPort) -> Self {
+        Self { input, output }
     }
 }
 
@@ -151,10 +262,9 @@ macro_rules! input {
     ($processor:ident, $input_name:ident, $setter:expr) => {
         #[derive(Debug, Default, Clone)]
         pub struct $input_name;
-        impl $crate::Input<$crate::DynProc> for $input_name {
-            fn set(&self, proc: $crate::SharedProc<$crate::DynProc>, value: f32) {
-                let processor = unsafe { &mut (*(proc.as_ptr() as *mut $processor)) };
-                $setter(processor, value);
+        impl $crate::Input<$processor> for $input_name {
+            fn set(&self, proc: &mut $processor, value: f32) {
+                $setter(proc, value);
             }
         }
     };
@@ -192,10 +302,9 @@ macro_rules! output {
     ($processor:ident, $output_name:ident, $getter:expr) => {
         #[derive(Debug, Default, Clone)]
         pub struct $output_name;
-        impl $crate::Output<$crate::DynProc> for $output_name {
-            fn get(&self, proc: $crate::SharedDynProc) -> f32 {
-                let processor = unsafe { &mut (*(proc.as_ptr() as *mut $processor)) };
-                $getter(processor)
+        impl $crate::Output<$processor> for $output_name {
+            fn get(&self, proc: &mut $processor) -> f32 {
+                $getter(proc)
             }
         }
     };
@@ -203,30 +312,40 @@ macro_rules! output {
 
 /// Macro to create an `OutputPort` in a short and more flexible way.
 ///
-/// It takes an instance of a struct that implements `Processor`.
+/// It takes the `Handle` of a processor added to a chain.
 /// It can optionally take the index of the port,
-/// if the processor has multiple ports.
+/// if the processor has multiple ports. Ports in
+/// nested tuples take their path followed by an
+/// explicit index, e.g. `make_output_port!(proc, 1, 0 => 2)`.
 #[macro_export]
 macro_rules! make_output_port {
-    ($proc:expr $(, $port_num:tt)*) => {
-        $crate::OutputPort {
-            proc: $proc.clone(),
-            port: Box::new($proc.clone().borrow().output $(. $port_num)* .clone()),
-        }
+    ($proc:expr) => {
+        $crate::OutputPort::of($proc, 0, |proc| &proc.output)
+    };
+    ($proc:expr, $port_num:tt) => {
+        $crate::OutputPort::of($proc, $port_num, |proc| &proc.output.$port_num)
+    };
+    ($proc:expr, $($port_num:tt),+ => $index:expr) => {
+        $crate::OutputPort::of($proc, $index, |proc| &proc.output $(. $port_num)+)
     };
 }
 
 /// Macro to create an `InputPort` in a short and more flexible way.
 ///
-/// It takes an instance of a struct that implements `Processor`.
+/// It takes the `Handle` of a processor added to a chain.
 /// It can optionally take the index of the port,
-/// if the processor has multiple ports.
+/// if the processor has multiple ports. Ports in
+/// nested tuples take their path followed by an
+/// explicit index, e.g. `make_input_port!(proc, 1, 0 => 2)`.
 #[macro_export]
 macro_rules! make_input_port {
-    ($proc:expr $(, $port_num:tt)*) => {
-        $crate::InputPort {
-            proc: $proc.clone(),
-            port: Box::new($proc.clone().borrow().input $(. $port_num)* .clone()),
-        }
+    ($proc:expr) => {
+        $crate::InputPort::of($proc, 0, |proc| &proc.input)
+    };
+    ($proc:expr, $port_num:tt) => {
+        $crate::InputPort::of($proc, $port_num, |proc| &proc.input.$port_num)
+    };
+    ($proc:expr, $($port_num:tt),+ => $index:expr) => {
+        $crate::InputPort::of($proc, $index, |proc| &proc.input $(. $port_num)+)
     };
 }
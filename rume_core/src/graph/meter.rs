@@ -0,0 +1,271 @@
+use crate::{convert::tick, lib::*, *};
+use core::sync::atomic::{fence, AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+/// The number of samples a meter
+/// endpoint measures by default.
+pub const METER_WINDOW: usize = 1024;
+
+/// The levels a meter endpoint measured
+/// over its latest window of samples.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MeterReading {
+    /// The largest absolute value.
+    pub peak: f32,
+    /// The root mean square of the values.
+    pub rms: f32,
+    /// Whether any value reached full scale.
+    pub clipped: bool,
+}
+
+/// The latest reading of a meter, behind a
+/// sequence number that is odd while it is
+/// being written. Readers retry until they
+/// read the same even number on both sides
+/// of the reading, the writer never waits.
+#[derive(Default)]
+struct Published {
+    sequence: AtomicUsize,
+    peak: AtomicU32,
+    rms: AtomicU32,
+    clipped: AtomicBool,
+}
+
+/// Publishes the readings of a meter endpoint.
+pub struct MeterWriter {
+    published: Arc<Published>,
+}
+
+impl MeterWriter {
+    fn publish(&mut self, reading: MeterReading) {
+        let sequence = self.published.sequence.load(Ordering::Relaxed);
+        self.published
+            .sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let published = &self.published;
+        published
+            .peak
+            .store(reading.peak.to_bits(), Ordering::Relaxed);
+        published
+            .rms
+            .store(reading.rms.to_bits(), Ordering::Relaxed);
+        published.clipped.store(reading.clipped, Ordering::Relaxed);
+
+        published
+            .sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+}
+
+/// Reads the latest reading of a meter
+/// endpoint from any thread, as often as
+/// it likes, without draining anything.
+#[derive(Clone)]
+pub struct MeterReader {
+    published: Arc<Published>,
+}
+
+impl MeterReader {
+    pub fn read(&self) -> MeterReading {
+        let published = &self.published;
+        loop {
+            let before = published.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                core::hint::spin_loop();
+                continue;
+            }
+
+            let reading = MeterReading {
+                peak: f32::from_bits(published.peak.load(Ordering::Relaxed)),
+                rms: f32::from_bits(published.rms.load(Ordering::Relaxed)),
+                clipped: published.clipped.load(Ordering::Relaxed),
+            };
+
+            fence(Ordering::Acquire);
+            if published.sequence.load(Ordering::Relaxed) == before {
+                return reading;
+            }
+        }
+    }
+}
+
+/// Create a meter writer and reader
+/// sharing the latest reading.
+pub fn make_meter_endpoint() -> (MeterWriter, MeterReader) {
+    let published = Arc::new(Published::default());
+    (
+        MeterWriter {
+            published: published.clone(),
+        },
+        MeterReader { published },
+    )
+}
+
+/// Measures what reaches its input over
+/// windows of samples, publishing the peak,
+/// RMS and clipping of each window for a
+/// `MeterReader` to poll.
+pub struct MeterEndpoint {
+    pub input: MeterEndpointInput,
+    writer: MeterWriter,
+    window: usize,
+    window_millis: Option<f32>,
+    value: f32,
+    count: usize,
+    peak: f32,
+    sum: f32,
+    clipped: bool,
+}
+
+input! { MeterEndpoint, MeterEndpointInput,
+    |proc: &mut MeterEndpoint, value: f32| {
+        proc.value = value;
+    }
+}
+
+impl MeterEndpoint {
+    pub fn new(writer: MeterWriter) -> Self {
+        Self {
+            input: MeterEndpointInput,
+            writer,
+            window: METER_WINDOW,
+            window_millis: None,
+            value: 0.0,
+            count: 0,
+            peak: 0.0,
+            sum: 0.0,
+            clipped: false,
+        }
+    }
+
+    /// Measure over windows of `samples`.
+    pub fn with_window(mut self, samples: usize) -> Self {
+        self.window = samples.max(1);
+        self.window_millis = None;
+        self
+    }
+
+    /// Measure over windows of `ms` milliseconds,
+    /// converted to samples at `prepare`.
+    pub fn with_window_millis(mut self, ms: f32) -> Self {
+        self.window_millis = Some(ms);
+        self
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+impl Processor for MeterEndpoint {
+    fn prepare(&mut self, config: AudioConfig) {
+        if let Some(ms) = self.window_millis {
+            let samples = tick::from_millis(ms, config.sample_rate as f32);
+            self.window = (samples as usize).max(1);
+        }
+        self.count = 0;
+        self.peak = 0.0;
+        self.sum = 0.0;
+        self.clipped = false;
+    }
+
+    fn process(&mut self) {
+        let magnitude = self.value.abs();
+        self.peak = self.peak.max(magnitude);
+        self.sum += self.value * self.value;
+        self.clipped |= magnitude >= 1.0;
+        self.count += 1;
+        self.value = 0.0;
+
+        if self.count >= self.window {
+            self.writer.publish(MeterReading {
+                peak: self.peak,
+                rms: libm::sqrtf(self.sum / self.count as f32),
+                clipped: self.clipped,
+            });
+            self.count = 0;
+            self.peak = 0.0;
+            self.sum = 0.0;
+            self.clipped = false;
+        }
+    }
+}
+
+impl IndexedPorts for MeterEndpoint {
+    fn input_port(processor: Handle<Self>, index: usize) -> Option<InputPort> {
+        match index {
+            0 => Some(make_input_port!(processor)),
+            _ => None,
+        }
+    }
+
+    fn output_port(_: Handle<Self>, _: usize) -> Option<OutputPort> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn measure(meter: &mut MeterEndpoint, values: &[f32]) {
+        for value in values {
+            MeterEndpointInput.set(meter, *value);
+            meter.process();
+        }
+    }
+
+    #[test]
+    fn readings_are_published_once_per_window() {
+        let (writer, reader) = make_meter_endpoint();
+        let mut meter = MeterEndpoint::new(writer).with_window(4);
+        meter.prepare(AudioConfig::default());
+
+        measure(&mut meter, &[0.5, -0.5, 0.5]);
+        assert_eq!(reader.read(), MeterReading::default());
+
+        measure(&mut meter, &[-0.5]);
+        let reading = reader.read();
+        assert_eq!(reading.peak, 0.5);
+        assert_eq!(reading.rms, 0.5);
+        assert!(!reading.clipped);
+
+        measure(&mut meter, &[0.0, -1.0, 0.0, 0.0]);
+        let reading = reader.read();
+        assert_eq!(reading.peak, 1.0);
+        assert_eq!(reading.rms, 0.5);
+        assert!(reading.clipped);
+    }
+
+    #[test]
+    fn windows_in_millis_follow_the_sample_rate() {
+        let (writer, _) = make_meter_endpoint();
+        let mut meter = MeterEndpoint::new(writer).with_window_millis(10.0);
+
+        meter.prepare(48_000.into());
+        assert_eq!(meter.window(), 480);
+
+        meter.prepare(8_000.into());
+        assert_eq!(meter.window(), 80);
+    }
+
+    #[test]
+    fn readings_are_not_torn_across_threads() {
+        let (writer, reader) = make_meter_endpoint();
+        let mut meter = MeterEndpoint::new(writer).with_window(1);
+        meter.prepare(AudioConfig::default());
+
+        let polling = std::thread::spawn(move || {
+            for _ in 0..10_000 {
+                let reading = reader.read();
+                assert_eq!(reading.peak, reading.rms);
+            }
+        });
+
+        for index in 0..100_000 {
+            measure(&mut meter, &[(index % 100) as f32 / 100.0]);
+        }
+        polling.join().unwrap();
+    }
+}
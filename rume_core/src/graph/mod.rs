@@ -11,9 +11,35 @@ pub use io::*;
 pub mod proc;
 pub use proc::*;
 
+pub mod node;
+pub use node::*;
+
 #[macro_use]
 pub mod chain;
 pub use chain::*;
 
+pub mod subgraph;
+pub use subgraph::*;
+
+pub mod dot;
+pub use dot::*;
+
+pub mod error;
+pub use error::*;
+
+#[cfg(feature = "std")]
+pub mod edit;
+#[cfg(feature = "std")]
+pub use edit::*;
+
+pub mod stream;
+pub use stream::*;
+
+pub mod clock;
+pub use clock::*;
+
 pub mod endpoints;
 pub use endpoints::*;
+
+pub mod meter;
+pub use meter::*;
@@ -0,0 +1,95 @@
+use crate::{lib::*, proc::*};
+use core::{any::Any, fmt, marker::PhantomData};
+
+/// Identifies a processor owned by a `SignalChain`.
+/// Ids are handed out in the order processors are
+/// added and are never reused within a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    /// The order in which the processor
+    /// was added to its chain.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A typed handle to a processor owned by a
+/// `SignalChain`. It is used to connect the
+/// processor's ports and to reach its state
+/// once the chain owns it.
+pub struct Handle<P> {
+    id: NodeId,
+    processor: PhantomData<fn() -> P>,
+}
+
+impl<P> Handle<P> {
+    pub(crate) fn new(id: NodeId) -> Self {
+        Self {
+            id,
+            processor: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+}
+
+impl<P> Clone for Handle<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for Handle<P> {}
+
+impl<P> PartialEq for Handle<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<P> fmt::Debug for Handle<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.id.0)
+    }
+}
+
+impl<P> From<Handle<P>> for NodeId {
+    fn from(handle: Handle<P>) -> Self {
+        handle.id
+    }
+}
+
+/// A `Processor` that a `SignalChain` can own.
+/// It is implemented for every processor that
+/// can be sent to another thread.
+pub trait AnyProcessor: Processor + Send {
+    fn as_any(&self) -> &dyn Any;
+    fn type_name(&self) -> &'static str;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<P> AnyProcessor for P
+where
+    P: Processor + Send + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<P>()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The processors owned by a `SignalChain`,
+/// indexed by `NodeId`. A removed processor
+/// leaves an empty slot behind.
+pub type Arena = Vec<Option<Box<dyn AnyProcessor>>>;
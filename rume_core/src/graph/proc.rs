@@ -1,9 +1,15 @@
-use crate::{io::*, lib::*};
+use crate::{
+    graph::{error::*, sort::*},
+    io::*,
+    lib::*,
+    node::*,
+};
 use core::{
-    cell::RefCell,
+    any::{Any, TypeId},
     ops::{Deref, DerefMut},
     option::Option,
 };
+use heapless::consts::U16;
 
 #[derive(Clone, Copy)]
 pub struct AudioConfig {
@@ -38,132 +44,740 @@ impl Into<AudioConfig> for f32 {
     }
 }
 
-/// A shared pointer to a generic type
-/// which will be expected to be a `Processor`.
-pub type SharedProc<P> = Rc<RefCell<P>>;
-
 /// A generic dynamic Processor.
 pub type DynProc = dyn Processor + 'static;
 
-/// A shared pointer to a dynamic `Processor`.
-pub type SharedDynProc = SharedProc<DynProc>;
-
-/// A weak pointer, i.e. a "view" of
-/// a generic type.
-pub type SharedProcView<P> = Weak<RefCell<P>>;
-
-/// A weak pointer to a dynamic `Processor`.
-pub type SharedDynProcView = SharedProcView<DynProc>;
-
 pub trait Processor {
     fn prepare(&mut self, config: AudioConfig);
     fn process(&mut self);
+
+    /// Expose a block based implementation
+    /// of this processor, if it has one.
+    /// A `SignalChain` will then render it
+    /// one buffer at a time instead of
+    /// calling `process` for every sample.
+    fn as_block_processor(&mut self) -> Option<&mut dyn BlockProcessor> {
+        None
+    }
+
+    /// The indices of the input ports that
+    /// must be connected for this processor
+    /// to work. A `SignalChain` with any of
+    /// them left unconnected fails to build.
+    fn required_inputs(&self) -> &[usize] {
+        &[]
+    }
+
+    /// How each input port, by index, combines
+    /// the data of several connections feeding
+    /// it. Ports past the end of the slice sum.
+    fn merges(&self) -> &[Merge] {
+        &[]
+    }
+}
+
+/// The maximum number of input or output
+/// ports a `BlockProcessor` can declare.
+pub type MaxBlockPorts = U16;
+
+/// `MaxBlockPorts` as a number.
+pub fn max_block_ports() -> usize {
+    heapless::Vec::<(), MaxBlockPorts>::new().capacity()
 }
 
-pub fn make_processor<P>(processor: P) -> SharedProc<P> {
-    Rc::new(RefCell::new(processor))
+/// A processor that renders a whole
+/// buffer per call. The buffers are
+/// indexed by port number, the same
+/// index used by `make_input_port!`
+/// and `make_output_port!`. Inputs
+/// that are not connected are silent.
+///
+/// ```
+///     use rume_core::*;
+///
+///     #[derive(Default)]
+///     pub struct Gain {
+///         pub input: GainInput,
+///         pub output: GainOutput,
+///     }
+///
+///     input! { Gain, GainInput, |_: &mut Gain, _: f32| {} }
+///     output! { Gain, GainOutput, |_: &mut Gain| -> f32 { 0.0 } }
+///
+///     impl Processor for Gain {
+///         fn prepare(&mut self, _: AudioConfig) {}
+///         fn process(&mut self) {}
+///
+///         fn as_block_processor(&mut self) -> Option<&mut dyn BlockProcessor> {
+///             Some(self)
+///         }
+///     }
+///
+///     impl BlockProcessor for Gain {
+///         fn num_inputs(&self) -> usize { 1 }
+///         fn num_outputs(&self) -> usize { 1 }
+///
+///         fn process_block(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+///             for (out, x) in outputs[0].iter_mut().zip(inputs[0]) {
+///                 *out = *x * 0.5;
+///             }
+///         }
+///     }
+/// ```
+pub trait BlockProcessor {
+    fn num_inputs(&self) -> usize;
+    fn num_outputs(&self) -> usize;
+    fn process_block(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]);
 }
 
-/// A `Processor` with views
-/// to its inputs and outputs.
-/// This is effectively a node
-/// in the linked-list.
+/// The `NodeId` of a processor
+/// with views to its inputs and
+/// outputs. This is effectively
+/// a node in the linked-list.
 pub struct ConnectedProcessor {
-    proc: SharedDynProc,
-    outs: Vec<DynConnection>,
+    id: NodeId,
+    outs: Vec<Connection>,
+    feedback: Vec<Connection>,
+    merges: Vec<(usize, Merge)>,
+    ports: Ports,
+    links: Links,
+}
+
+/// What a processor declares about its ports.
+/// It is read once, when the processor is added,
+/// so that a schedule can be linked again without
+/// the processors, which the chain owns.
+#[derive(Clone)]
+struct Ports {
+    /// The type of the processor, which
+    /// the ports connected to it belong to.
+    processor: TypeId,
+    /// The name of the processor's type.
+    name: &'static str,
+    /// `(inputs, outputs)` of a block processor.
+    block: Option<(usize, usize)>,
+    required: Vec<usize>,
+    merges: Vec<Merge>,
+}
+
+impl Ports {
+    fn of(proc: &mut dyn AnyProcessor) -> Self {
+        let block = proc
+            .as_block_processor()
+            .map(|block| (block.num_inputs(), block.num_outputs()));
+
+        Self {
+            processor: Any::type_id(proc.as_any()),
+            name: proc.type_name(),
+            block,
+            required: proc.required_inputs().to_vec(),
+            merges: proc.merges().to_vec(),
+        }
+    }
+}
+
+/// Where a connection is stored
+/// on the processor it leaves.
+#[derive(Clone, Copy)]
+enum Edge {
+    Forward(usize),
+    Feedback(usize),
+}
+
+/// The buffers a processor reads from
+/// and writes to when rendering blocks.
+/// Each entry is an index into the
+/// buffers owned by `ConnectedProcessors`.
+#[derive(Default)]
+struct Links {
+    /// A buffer per input port index.
+    inputs: Vec<usize>,
+    /// A buffer per output port index.
+    outputs: Vec<usize>,
+    /// `(input, processor, connection)` for every
+    /// input port that is connected, pointing at
+    /// a connection that holds that port.
+    sources: Vec<(usize, usize, Edge)>,
+    /// `(connection, buffer)` for every
+    /// output port that is connected.
+    sinks: Vec<(Edge, usize)>,
+    /// Inputs fed by several connections, merged
+    /// into their own buffer before rendering.
+    mixes: Vec<Mix>,
+}
+
+struct Mix {
+    merge: Merge,
+    sources: Vec<usize>,
+    buffer: usize,
+}
+
+impl Mix {
+    fn render(&self, buffers: &mut [Vec<f32>], num_samples: usize) {
+        let mut mixed = core::mem::take(&mut buffers[self.buffer]);
+        let (first, rest) = self.sources.split_first().unwrap();
+
+        mixed[..num_samples].copy_from_slice(&buffers[*first][..num_samples]);
+        for source in rest {
+            let source = &buffers[*source][..num_samples];
+            for (merged, value) in mixed.iter_mut().zip(source) {
+                *merged = self.merge.apply(*merged, *value);
+            }
+        }
+
+        buffers[self.buffer] = mixed;
+    }
 }
 
 impl ConnectedProcessor {
-    pub fn new(proc: SharedDynProc) -> Self {
+    pub fn new(id: NodeId, proc: &mut dyn AnyProcessor) -> Self {
         Self {
-            proc,
+            id,
+            ports: Ports::of(proc),
             outs: Vec::new(),
+            feedback: Vec::new(),
+            merges: Vec::new(),
+            links: Links::default(),
         }
     }
 
-    pub fn add_output(&mut self, connection: DynConnection) {
+    pub fn add_output(&mut self, connection: Connection) {
         if self.outs.iter().find(|c| **c == connection).is_none() {
             self.outs.push(connection);
         }
     }
 
-    pub fn outs(&self) -> &[DynConnection] {
+    /// Add a connection that closes a loop.
+    /// It is left out of the ordering and
+    /// delivers its data one block later.
+    pub fn add_feedback(&mut self, connection: Connection) {
+        if self.feedback.iter().find(|c| **c == connection).is_none() {
+            self.feedback.push(connection);
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The name of the processor's type,
+    /// without the path to its module.
+    pub fn name(&self) -> &'static str {
+        let name = self.ports.name;
+        let end = name.find('<').unwrap_or(name.len());
+        let start = name[..end].rfind("::").map_or(0, |start| start + 2);
+        &name[start..]
+    }
+
+    pub fn outs(&self) -> &[Connection] {
         self.outs.as_slice()
     }
 
-    pub fn outs_mut(&mut self) -> &mut [DynConnection] {
+    pub fn outs_mut(&mut self) -> &mut [Connection] {
         self.outs.as_mut_slice()
     }
-}
 
-impl Processor for ConnectedProcessor {
-    fn prepare(&mut self, config: AudioConfig) {
-        self.proc.borrow_mut().prepare(config);
+    pub fn feedback(&self) -> &[Connection] {
+        self.feedback.as_slice()
+    }
+
+    /// Override how an input port combines
+    /// the data of several connections.
+    pub fn set_merge(&mut self, input: usize, merge: Merge) {
+        self.merges.retain(|(i, _)| *i != input);
+        self.merges.push((input, merge));
+    }
+
+    pub fn merge(&self, input: usize) -> Merge {
+        match self.merges.iter().find(|(i, _)| *i == input) {
+            Some((_, merge)) => *merge,
+            None => self.ports.merges.get(input).copied().unwrap_or_default(),
+        }
+    }
+
+    /// The number of `(inputs, outputs)` of
+    /// the processor, if it renders blocks.
+    pub fn block_ports(&self) -> Option<(usize, usize)> {
+        self.ports.block
+    }
+
+    pub fn required_inputs(&self) -> &[usize] {
+        self.ports.required.as_slice()
+    }
+
+    /// Remove every connection leaving
+    /// this processor for `processor`.
+    fn disconnect_from(&mut self, processor: NodeId) {
+        self.outs.retain(|con| con.input.node() != processor);
+        self.feedback.retain(|con| con.input.node() != processor);
+    }
+
+    fn connection(&self, edge: Edge) -> &Connection {
+        match edge {
+            Edge::Forward(i) => &self.outs[i],
+            Edge::Feedback(i) => &self.feedback[i],
+        }
+    }
+
+    fn connections(&self) -> impl Iterator<Item = (Edge, &Connection)> {
+        let outs = self.outs.iter().enumerate();
+        let feedback = self.feedback.iter().enumerate();
+        outs.map(|(i, con)| (Edge::Forward(i), con))
+            .chain(feedback.map(|(i, con)| (Edge::Feedback(i), con)))
     }
+}
 
-    fn process(&mut self) {
-        self.proc.borrow_mut().process();
-        self.outs.iter_mut().for_each(|con| con.transfer());
+impl Clone for ConnectedProcessor {
+    /// Copy the processor and its connections,
+    /// without the buffers it was linked to.
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            outs: self.outs.clone(),
+            feedback: self.feedback.clone(),
+            merges: self.merges.clone(),
+            ports: self.ports.clone(),
+            links: Links::default(),
+        }
     }
 }
 
+/// The index of the buffer that is
+/// always silent. It feeds every
+/// input that is not connected.
+const SILENCE: usize = 0;
+
 /// A wrapper around a list
 /// of processors. This is
 /// effectively the linked-list.
-#[derive(Default)]
 pub struct ConnectedProcessors {
     inner: Vec<ConnectedProcessor>,
+    buffers: Vec<Vec<f32>>,
+    /// `(output, delayed)` buffer pairs for every
+    /// feedback connection, copied after each block.
+    delays: Vec<(usize, usize)>,
+    block_size: usize,
+}
+
+impl Default for ConnectedProcessors {
+    fn default() -> Self {
+        Self {
+            inner: Vec::new(),
+            buffers: Vec::new(),
+            delays: Vec::new(),
+            block_size: AudioConfig::default().buffer_size,
+        }
+    }
 }
 
 impl ConnectedProcessors {
-    pub fn push(&mut self, processor: SharedDynProc) {
-        if self
-            .inner
+    pub fn push(&mut self, id: NodeId, processor: &mut dyn AnyProcessor) {
+        if self.index_of(id).is_none() {
+            self.inner.push(ConnectedProcessor::new(id, processor));
+        }
+    }
+
+    pub fn index_of(&self, id: NodeId) -> Option<usize> {
+        self.inner.iter().position(|candidate| candidate.id == id)
+    }
+
+    /// Rearrange the processors so that the
+    /// processor at `order[i]` moves to `i`.
+    pub fn order(&mut self, order: Vec<usize>) {
+        let mut processors: Vec<Option<ConnectedProcessor>> =
+            self.inner.drain(..).map(Some).collect();
+
+        self.inner = order
             .iter()
-            .filter(|p| Rc::ptr_eq(&p.proc, &processor))
-            .count()
-            == 0
-        {
-            self.inner.push(ConnectedProcessor::new(processor));
+            .map(|i| processors[*i].take().expect("Processor ordered twice"))
+            .collect();
+    }
+
+    pub fn find(&self, id: NodeId) -> Option<&ConnectedProcessor> {
+        let idx = self.index_of(id)?;
+        Some(&self.inner[idx])
+    }
+
+    pub fn find_mut(&mut self, id: NodeId) -> Option<&mut ConnectedProcessor> {
+        let idx = self.index_of(id)?;
+        Some(&mut self.inner[idx])
+    }
+
+    /// Remove a processor along with every
+    /// connection to and from it.
+    pub fn remove(&mut self, id: NodeId) -> Result<(), GraphError> {
+        let idx = self.index_of(id).ok_or(GraphError::UnknownProcessor)?;
+
+        self.inner.remove(idx);
+        for node in self.inner.iter_mut() {
+            node.disconnect_from(id);
         }
+        Ok(())
     }
 
-    pub fn index_of(&self, processor: SharedDynProc) -> Option<usize> {
-        for (i, candidate) in self.inner.iter().enumerate() {
-            if Rc::ptr_eq(&candidate.proc, &processor) {
-                return Some(i);
+    /// Connect an output to an input, as a feedback
+    /// connection if `feedback` is set. Both ports'
+    /// processors must already be in the list.
+    pub fn connect(
+        &mut self,
+        output: OutputPort,
+        input: InputPort,
+        feedback: bool,
+    ) -> Result<(), GraphError> {
+        let tx = self
+            .index_of(output.node())
+            .ok_or(GraphError::UnknownProcessor)?;
+        let rx = self
+            .index_of(input.node())
+            .ok_or(GraphError::UnknownProcessor)?;
+
+        if output.processor() != self.inner[tx].ports.processor {
+            return Err(GraphError::MismatchedPort {
+                processor: output.node().index(),
+                port: output.index(),
+            });
+        }
+
+        if input.processor() != self.inner[rx].ports.processor {
+            return Err(GraphError::MismatchedPort {
+                processor: input.node().index(),
+                port: input.index(),
+            });
+        }
+
+        if let Some((_, num_outputs)) = self.inner[tx].block_ports() {
+            if output.index() >= num_outputs {
+                return Err(GraphError::PortOutOfRange {
+                    processor: output.node().index(),
+                    port: output.index(),
+                });
             }
         }
-        None
+
+        if let Some((num_inputs, _)) = self.inner[rx].block_ports() {
+            if input.index() >= num_inputs {
+                return Err(GraphError::PortOutOfRange {
+                    processor: input.node().index(),
+                    port: input.index(),
+                });
+            }
+        }
+
+        let connection = Connection::new(output, input);
+        let node = &mut self.inner[tx];
+
+        if node.outs().contains(&connection) || node.feedback().contains(&connection) {
+            return Err(GraphError::DuplicateConnection {
+                output: connection.output.node().index(),
+                input: connection.input.node().index(),
+            });
+        }
+
+        match feedback {
+            true => node.add_feedback(connection),
+            false => node.add_output(connection),
+        }
+        Ok(())
     }
 
-    pub fn order(&mut self, order: Vec<usize>) {
-        for (i, j) in order.iter().enumerate().take(order.len() / 2) {
-            self.inner.swap(i, *j);
+    /// Remove the connection between an output
+    /// and an input, whether it is a feedback
+    /// connection or not.
+    pub fn disconnect(&mut self, output: OutputPort, input: InputPort) -> Result<(), GraphError> {
+        let tx = self
+            .index_of(output.node())
+            .ok_or(GraphError::UnknownProcessor)?;
+        self.index_of(input.node())
+            .ok_or(GraphError::UnknownProcessor)?;
+
+        let connection = Connection::new(output, input);
+        let node = &mut self.inner[tx];
+        let count = node.outs.len() + node.feedback.len();
+
+        node.outs.retain(|con| *con != connection);
+        node.feedback.retain(|con| *con != connection);
+
+        if node.outs.len() + node.feedback.len() == count {
+            return Err(GraphError::UnknownConnection {
+                output: connection.output.node().index(),
+                input: connection.input.node().index(),
+            });
         }
+        Ok(())
     }
 
-    pub fn find(&self, processor: SharedDynProc) -> Option<&ConnectedProcessor> {
-        let idx = self.index_of(processor)?;
-        Some(&self.inner[idx])
+    /// A copy of the processors and their
+    /// connections, without any buffers.
+    /// It must be scheduled before it renders.
+    pub fn topology(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            block_size: self.block_size,
+            ..Self::default()
+        }
     }
 
-    pub fn find_mut(&mut self, processor: SharedDynProc) -> Option<&mut ConnectedProcessor> {
-        let idx = self.index_of(processor)?;
-        Some(&mut self.inner[idx])
+    /// Check that every required input is connected
+    /// and that every block processor fits in a block,
+    /// sort the processors so that each one renders
+    /// after the processors feeding it and link them.
+    pub fn schedule(&mut self) -> Result<(), GraphError> {
+        self.check_required_inputs()?;
+        self.check_block_ports()?;
+
+        let order = TopologicalSort::sort(self).map_err(|Cycle(nodes)| {
+            GraphError::Cycle(nodes.iter().map(|i| self.inner[*i].id.index()).collect())
+        })?;
+        self.order(order);
+        self.link();
+        Ok(())
+    }
+
+    fn check_required_inputs(&self) -> Result<(), GraphError> {
+        for node in self.inner.iter() {
+            for port in node.required_inputs() {
+                let is_connected = self.inner.iter().any(|n| {
+                    n.connections()
+                        .any(|(_, con)| con.input.node() == node.id && con.input.index() == *port)
+                });
+
+                if !is_connected {
+                    return Err(GraphError::UnconnectedInput {
+                        processor: node.id.index(),
+                        port: *port,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_block_ports(&self) -> Result<(), GraphError> {
+        for node in self.inner.iter() {
+            if let Some((inputs, outputs)) = node.block_ports() {
+                let num_ports = inputs.max(outputs);
+                if num_ports > max_block_ports() {
+                    return Err(GraphError::TooManyPorts(num_ports));
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
-impl Processor for ConnectedProcessors {
-    fn prepare(&mut self, config: AudioConfig) {
-        self.inner.iter_mut().for_each(|proc| proc.prepare(config));
+impl Sortable for ConnectedProcessors {
+    fn next_nodes(&self, index: usize) -> Vec<usize> {
+        self.inner[index]
+            .outs()
+            .iter()
+            .filter_map(|con| self.index_of(con.input.node()))
+            .collect()
     }
 
-    fn process(&mut self) {
-        self.inner.iter_mut().for_each(|proc| proc.process());
+    fn num_nodes(&self) -> usize {
+        self.inner.len()
     }
 }
 
+impl ConnectedProcessors {
+    /// The maximum number of samples
+    /// that can be rendered in one block.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Resolve every connection to the buffers used
+    /// for block rendering. This must be called once
+    /// the processors are in their final order.
+    pub fn link(&mut self) {
+        self.buffers.clear();
+        self.buffers.push(Vec::new());
+        self.delays.clear();
+
+        for node in self.inner.iter_mut() {
+            let (num_inputs, num_outputs) = node.block_ports().unwrap_or((0, 0));
+
+            let num_outputs = node
+                .connections()
+                .map(|(_, con)| con.output.index() + 1)
+                .fold(num_outputs, usize::max);
+
+            let first_buffer = self.buffers.len();
+            self.buffers
+                .resize_with(first_buffer + num_outputs, Vec::new);
+
+            node.links = Links {
+                inputs: vec![SILENCE; num_inputs],
+                outputs: (first_buffer..first_buffer + num_outputs).collect(),
+                ..Links::default()
+            };
+
+            let mut sinks = Vec::new();
+            for (edge, con) in node.connections() {
+                let buffer = node.links.outputs[con.output.index()];
+                if sinks.iter().all(|(_, b)| *b != buffer) {
+                    sinks.push((edge, buffer));
+                }
+            }
+            node.links.sinks = sinks;
+        }
+
+        for dest in 0..self.inner.len() {
+            // The buffers feeding each input port.
+            let mut feeds: Vec<Vec<usize>> = Vec::new();
+            let mut sources = Vec::new();
+
+            for (src, node) in self.inner.iter().enumerate() {
+                for (edge, con) in node.connections() {
+                    if con.input.node() != self.inner[dest].id {
+                        continue;
+                    }
+
+                    let mut buffer = node.links.outputs[con.output.index()];
+                    if let Edge::Feedback(_) = edge {
+                        let delayed = self.buffers.len();
+                        self.buffers.push(Vec::new());
+                        self.delays.push((buffer, delayed));
+                        buffer = delayed;
+                    }
+
+                    let port = con.input.index();
+                    if feeds.len() <= port {
+                        feeds.resize_with(port + 1, Vec::new);
+                    }
+                    if feeds[port].is_empty() {
+                        sources.push((port, src, edge));
+                    }
+                    feeds[port].push(buffer);
+                }
+            }
+
+            let mut inputs = core::mem::take(&mut self.inner[dest].links.inputs);
+            let mut mixes = Vec::new();
+
+            if inputs.len() < feeds.len() {
+                inputs.resize(feeds.len(), SILENCE);
+            }
+
+            for (port, feed) in feeds.into_iter().enumerate() {
+                inputs[port] = match feed.len() {
+                    0 => SILENCE,
+                    1 => feed[0],
+                    _ => {
+                        let buffer = self.buffers.len();
+                        self.buffers.push(Vec::new());
+                        mixes.push(Mix {
+                            merge: self.inner[dest].merge(port),
+                            sources: feed,
+                            buffer,
+                        });
+                        buffer
+                    }
+                };
+            }
+
+            let links = &mut self.inner[dest].links;
+            links.inputs = inputs;
+            links.sources = sources;
+            links.mixes = mixes;
+        }
+
+        self.allocate(self.block_size);
+    }
+
+    /// Render `num_samples` through every processor,
+    /// one processor at a time. `num_samples` must
+    /// not exceed the block size.
+    pub fn render(&mut self, processors: &mut Arena, num_samples: usize) {
+        assert!(num_samples <= self.block_size);
+        (0..self.inner.len()).for_each(|i| self.render_processor(processors, i, num_samples));
+
+        for (output, delayed) in self.delays.iter() {
+            // Delayed buffers are always allocated
+            // after every processor's outputs.
+            let (outputs, delayed) = self.buffers.split_at_mut(*delayed);
+            delayed[0][..num_samples].copy_from_slice(&outputs[*output][..num_samples]);
+        }
+    }
+
+    pub(crate) fn allocate(&mut self, block_size: usize) {
+        self.block_size = block_size.max(1);
+        for buffer in self.buffers.iter_mut() {
+            buffer.clear();
+            buffer.resize(self.block_size, 0.0);
+        }
+    }
+
+    fn render_processor(&mut self, processors: &mut Arena, index: usize, num_samples: usize) {
+        let Self { inner, buffers, .. } = self;
+        let node = &inner[index];
+        let proc = processors[node.id.index()]
+            .as_deref_mut()
+            .expect("Processor missing from the chain");
+
+        for mix in node.links.mixes.iter() {
+            mix.render(buffers, num_samples);
+        }
+
+        if node.block_ports().is_some() {
+            let block = proc.as_block_processor().unwrap();
+            let (inputs, mut outputs) = split_buffers(buffers, &node.links, num_samples);
+            block.process_block(&inputs, &mut outputs);
+            return;
+        }
+
+        #[allow(clippy::needless_range_loop)]
+        for n in 0..num_samples {
+            for (port, src, edge) in node.links.sources.iter() {
+                let con = inner[*src].connection(*edge);
+                con.input
+                    .set(proc.as_any_mut(), buffers[node.links.inputs[*port]][n]);
+            }
+
+            proc.process();
+
+            for (edge, buffer) in node.links.sinks.iter() {
+                buffers[*buffer][n] = node.connection(*edge).output.get(proc.as_any_mut());
+            }
+        }
+    }
+}
+
+type BlockInputs<'a> = heapless::Vec<&'a [f32], MaxBlockPorts>;
+type BlockOutputs<'a> = heapless::Vec<&'a mut [f32], MaxBlockPorts>;
+
+/// Borrow the buffers a block processor reads from
+/// and the buffers it writes to, in port order.
+fn split_buffers<'a>(
+    buffers: &'a mut [Vec<f32>],
+    links: &Links,
+    num_samples: usize,
+) -> (BlockInputs<'a>, BlockOutputs<'a>) {
+    let mut inputs: heapless::Vec<Option<&'a [f32]>, MaxBlockPorts> =
+        links.inputs.iter().map(|_| None).collect();
+    let mut outputs: heapless::Vec<Option<&'a mut [f32]>, MaxBlockPorts> =
+        links.outputs.iter().map(|_| None).collect();
+
+    for (i, buffer) in buffers.iter_mut().enumerate() {
+        if let Some(port) = links.outputs.iter().position(|b| *b == i) {
+            outputs[port] = Some(&mut buffer[..num_samples]);
+            continue;
+        }
+
+        let buffer: &'a Vec<f32> = buffer;
+        for (port, _) in links.inputs.iter().enumerate().filter(|(_, b)| **b == i) {
+            inputs[port] = Some(&buffer[..num_samples]);
+        }
+    }
+
+    (
+        inputs.into_iter().map(Option::unwrap).collect(),
+        outputs.into_iter().map(Option::unwrap).collect(),
+    )
+}
+
 impl Deref for ConnectedProcessors {
     type Target = Vec<ConnectedProcessor>;
 
@@ -181,6 +795,7 @@ impl DerefMut for ConnectedProcessors {
 #[cfg(test)]
 pub mod dummies {
     use super::*;
+    use crate::chain::*;
 
     #[derive(Default)]
     pub struct DummyProcessor {
@@ -206,8 +821,12 @@ pub mod dummies {
         }
     }
 
-    pub fn dummy() -> SharedProc<DummyProcessor> {
-        make_processor(DummyProcessor::default())
+    pub fn set_value(chain: &mut SignalChain, processor: Handle<DummyProcessor>, value: f32) {
+        DummyInput.set(chain.processor_mut(processor).unwrap(), value);
+    }
+
+    pub fn value(chain: &mut SignalChain, processor: Handle<DummyProcessor>) -> f32 {
+        DummyOutput.get(chain.processor_mut(processor).unwrap())
     }
 
     #[derive(Default)]
@@ -257,6 +876,97 @@ pub mod dummies {
             proc.value
         }
     }
+
+    #[derive(Default)]
+    pub struct CounterProcessor {
+        pub input: (),
+        pub output: CounterOutput,
+        count: f32,
+    }
+
+    impl Processor for CounterProcessor {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {
+            self.count += 1.0;
+        }
+    }
+
+    output! { CounterProcessor, CounterOutput,
+        |proc: &mut CounterProcessor| -> f32 {
+            proc.count
+        }
+    }
+
+    /// Doubles its first input and
+    /// sums both inputs into its
+    /// second output, a block at a time.
+    #[derive(Default)]
+    pub struct BlockProcessorDummy {
+        pub input: (BlockDummyInput, BlockDummyInput),
+        pub output: (BlockDummyOutput, BlockDummyOutput),
+        pub num_blocks: usize,
+    }
+
+    impl Processor for BlockProcessorDummy {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {}
+
+        fn as_block_processor(&mut self) -> Option<&mut dyn BlockProcessor> {
+            Some(self)
+        }
+    }
+
+    impl BlockProcessor for BlockProcessorDummy {
+        fn num_inputs(&self) -> usize {
+            2
+        }
+
+        fn num_outputs(&self) -> usize {
+            2
+        }
+
+        fn process_block(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+            self.num_blocks += 1;
+            for n in 0..inputs[0].len() {
+                outputs[0][n] = inputs[0][n] * 2.0;
+                outputs[1][n] = inputs[0][n] + inputs[1][n];
+            }
+        }
+    }
+
+    /// Declares more inputs than
+    /// a block processor can have.
+    #[derive(Default)]
+    pub struct WideBlockDummy;
+
+    impl Processor for WideBlockDummy {
+        fn prepare(&mut self, _: AudioConfig) {}
+        fn process(&mut self) {}
+
+        fn as_block_processor(&mut self) -> Option<&mut dyn BlockProcessor> {
+            Some(self)
+        }
+    }
+
+    impl BlockProcessor for WideBlockDummy {
+        fn num_inputs(&self) -> usize {
+            max_block_ports() + 1
+        }
+
+        fn num_outputs(&self) -> usize {
+            1
+        }
+
+        fn process_block(&mut self, _: &[&[f32]], _: &mut [&mut [f32]]) {}
+    }
+
+    input! { BlockProcessorDummy, BlockDummyInput,
+        |_: &mut BlockProcessorDummy, _: f32| {}
+    }
+
+    output! { BlockProcessorDummy, BlockDummyOutput,
+        |_: &mut BlockProcessorDummy| -> f32 { 0.0 }
+    }
 }
 
 #[cfg(test)]
@@ -270,17 +980,18 @@ mod test {
         fn process(&mut self) {}
     }
 
-    fn dummy() -> SharedDynProc {
-        make_processor(Proc::default())
+    fn push(processors: &mut ConnectedProcessors, id: usize) -> NodeId {
+        let id = NodeId(id);
+        processors.push(id, &mut Proc::default());
+        id
     }
 
     #[test]
     fn pushing_same_proc_only_adds_the_first() {
-        let (a, b) = (dummy(), dummy());
         let mut processors = ConnectedProcessors::default();
-        processors.push(a.clone());
-        processors.push(a.clone());
-        processors.push(b.clone());
+        let a = push(&mut processors, 0);
+        push(&mut processors, 0);
+        let b = push(&mut processors, 1);
 
         assert_eq!(processors.len(), 2);
         assert_eq!(processors.index_of(a).unwrap(), 0);
@@ -291,17 +1002,13 @@ mod test {
     fn reordering_processors_swaps_their_positions() {
         const NUM_PROCESSORS: usize = 6;
 
-        let mut dummies = Vec::<SharedDynProc>::with_capacity(NUM_PROCESSORS);
         let mut processors = ConnectedProcessors::default();
-
-        for _ in 0..NUM_PROCESSORS {
-            let d = dummy();
-            dummies.push(d.clone());
-            processors.push(d.clone())
-        }
+        let ids: Vec<_> = (0..NUM_PROCESSORS)
+            .map(|i| push(&mut processors, i))
+            .collect();
 
         for (i, p) in processors.iter().enumerate() {
-            assert_eq!(processors.index_of(p.proc.clone()), Some(i))
+            assert_eq!(processors.index_of(p.id()), Some(i))
         }
 
         processors.swap(0, 3);
@@ -309,8 +1016,22 @@ mod test {
         processors.swap(2, 4);
 
         assert_eq!(processors.len(), NUM_PROCESSORS);
-        assert_eq!(processors.index_of(dummies[0].clone()), Some(3));
-        assert_eq!(processors.index_of(dummies[1].clone()), Some(5));
-        assert_eq!(processors.index_of(dummies[2].clone()), Some(4));
+        assert_eq!(processors.index_of(ids[0]), Some(3));
+        assert_eq!(processors.index_of(ids[1]), Some(5));
+        assert_eq!(processors.index_of(ids[2]), Some(4));
+    }
+
+    #[test]
+    fn ordering_processors_applies_the_permutation() {
+        const ORDER: [usize; 5] = [3, 0, 4, 1, 2];
+
+        let mut processors = ConnectedProcessors::default();
+        let ids: Vec<_> = (0..ORDER.len()).map(|i| push(&mut processors, i)).collect();
+
+        processors.order(ORDER.to_vec());
+
+        for (i, j) in ORDER.iter().enumerate() {
+            assert_eq!(processors.index_of(ids[*j]), Some(i));
+        }
     }
 }
@@ -5,9 +5,23 @@ pub trait Sortable {
     fn num_nodes(&self) -> usize;
 }
 
+/// The nodes that form a cycle, in the
+/// order they are connected. The last
+/// node connects back to the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cycle(pub Vec<usize>);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Unvisited,
+    Visiting,
+    Visited,
+}
+
 pub struct TopologicalSort<'a> {
     ordering: Vec<usize>,
-    visited: Vec<bool>,
+    marks: Vec<Mark>,
+    path: Vec<usize>,
     sortable: &'a dyn Sortable,
 }
 
@@ -16,40 +30,48 @@ impl<'a> TopologicalSort<'a> {
         let length = sortable.num_nodes();
         Self {
             ordering: Vec::<usize>::with_capacity(length),
-            visited: vec![false; length],
+            marks: vec![Mark::Unvisited; length],
+            path: Vec::<usize>::with_capacity(length),
             sortable,
         }
     }
 
-    pub fn sort(sortable: &'a dyn Sortable) -> Vec<usize> {
+    /// Order the nodes so that every node comes
+    /// before the nodes it connects to. Fails with
+    /// the first cycle found, since a cycle has
+    /// no such order.
+    pub fn sort(sortable: &'a dyn Sortable) -> Result<Vec<usize>, Cycle> {
         let mut sorter = TopologicalSort::new(sortable);
 
         for i in 0..sortable.num_nodes() {
-            if !sorter.visited[i] {
-                sorter.sort_inner(i);
+            if sorter.marks[i] == Mark::Unvisited {
+                sorter.sort_inner(i)?;
             }
         }
 
         sorter.ordering.reverse();
-        sorter.ordering
-    }
-
-    pub fn reverse_sort(sortable: &'a dyn Sortable) -> Vec<usize> {
-        let mut order = Self::sort(sortable);
-        order.reverse();
-        order
+        Ok(sorter.ordering)
     }
 
-    fn sort_inner(&mut self, index: usize) {
-        self.visited[index] = true;
+    fn sort_inner(&mut self, index: usize) -> Result<(), Cycle> {
+        self.marks[index] = Mark::Visiting;
+        self.path.push(index);
 
         for i in self.sortable.next_nodes(index) {
-            if !self.visited[i] {
-                self.sort_inner(i);
+            match self.marks[i] {
+                Mark::Unvisited => self.sort_inner(i)?,
+                Mark::Visiting => {
+                    let start = self.path.iter().position(|node| *node == i).unwrap();
+                    return Err(Cycle(self.path[start..].to_vec()));
+                }
+                Mark::Visited => {}
             }
         }
 
+        self.path.pop();
+        self.marks[index] = Mark::Visited;
         self.ordering.push(index);
+        Ok(())
     }
 }
 
@@ -129,7 +151,7 @@ mod test {
             assert!(graph.next_nodes(NUM_NODES - 1).contains(&i));
         }
 
-        let sorted_order = TopologicalSort::sort(&graph);
+        let sorted_order = TopologicalSort::sort(&graph).unwrap();
         assert_eq!(sorted_order.len(), graph.nodes.len());
         assert_eq!(sorted_order[0], NUM_NODES - 1);
     }
@@ -145,7 +167,7 @@ mod test {
         graph.connect(1, 3);
         graph.connect(3, 4);
 
-        let order = TopologicalSort::sort(&graph);
+        let order = TopologicalSort::sort(&graph).unwrap();
         assert_eq!(order[0], 0);
         assert!([1, 2].contains(&order[1]));
         assert!([1, 2].contains(&order[2]));
@@ -169,7 +191,7 @@ mod test {
         graph.connect(3, 0);
         graph.connect(1, 6);
 
-        let order = TopologicalSort::sort(&graph);
+        let order = TopologicalSort::sort(&graph).unwrap();
         assert_eq!(order[0], 7);
         assert_eq!(order[1], 5);
         assert_eq!(order[2], 4);
@@ -179,4 +201,45 @@ mod test {
         assert_eq!(order[6], 6);
         assert_eq!(order[7], 0);
     }
+
+    ///
+    ///  [0] -> [1] -> [2] -> [3]
+    ///           ^-----------|
+    #[test]
+    fn cycle_is_reported_with_its_nodes() {
+        let mut graph = Graph::with_size(4);
+        graph.connect(0, 1);
+        graph.connect(1, 2);
+        graph.connect(2, 3);
+        graph.connect(3, 1);
+
+        assert_eq!(TopologicalSort::sort(&graph), Err(Cycle(vec![1, 2, 3])));
+    }
+
+    ///
+    ///  [0] -> [1] -> [1]
+    #[test]
+    fn node_connected_to_itself_is_a_cycle() {
+        let mut graph = Graph::with_size(2);
+        graph.connect(0, 1);
+        graph.connect(1, 1);
+
+        assert_eq!(TopologicalSort::sort(&graph), Err(Cycle(vec![1])));
+    }
+
+    ///
+    ///  [0] -> [1] -> [3]
+    ///    |--> [2] ----^
+    #[test]
+    fn converging_paths_are_not_a_cycle() {
+        let mut graph = Graph::with_size(4);
+        graph.connect(0, 1);
+        graph.connect(0, 2);
+        graph.connect(1, 3);
+        graph.connect(2, 3);
+
+        let order = TopologicalSort::sort(&graph).unwrap();
+        assert_eq!(order[0], 0);
+        assert_eq!(order[3], 3);
+    }
 }
@@ -0,0 +1,840 @@
+use crate::lib::*;
+use core::{
+    cell::Cell,
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The most channels a stream can interleave.
+pub const MAX_CHANNELS: usize = 16;
+
+/// What a producer does with a value
+/// pushed into a full stream. While the
+/// consumer is reading, the queued values
+/// are left to it and the value being
+/// pushed is dropped whatever the policy.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    /// Keep the queued values and
+    /// drop the value being pushed.
+    #[default]
+    DropNewest,
+    /// Drop the oldest queued value
+    /// to make room for the new one.
+    DropOldest,
+    /// Replace the newest queued value
+    /// with the value being pushed.
+    Overwrite,
+}
+
+/// What a consumer reads from
+/// an empty stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Underflow {
+    /// The default value of the
+    /// stream, i.e. silence.
+    #[default]
+    Silence,
+    /// The last value read again.
+    Hold,
+}
+
+/// Where the slots of a stream live:
+/// allocated with the stream or in a
+/// buffer handed over by the caller.
+enum Slots<T: 'static> {
+    Heap(Box<[Cell<T>]>),
+    Borrowed(&'static [Cell<T>]),
+}
+
+impl<T> Deref for Slots<T> {
+    type Target = [Cell<T>];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Slots::Heap(slots) => slots,
+            Slots::Borrowed(slots) => slots,
+        }
+    }
+}
+
+/// The bits of `State::pack` each
+/// of `head` and `tail` is kept in.
+const COUNT_BITS: u32 = (usize::BITS - 1) / 2;
+const COUNT_MASK: usize = (1 << COUNT_BITS) - 1;
+
+/// Where the queued values of a ring start
+/// and end, and whether the consumer is
+/// reading them, packed into one word so
+/// that both halves change them together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct State {
+    head: usize,
+    tail: usize,
+    reading: bool,
+}
+
+impl State {
+    fn pack(self) -> usize {
+        self.tail << (COUNT_BITS + 1) | self.head << 1 | self.reading as usize
+    }
+
+    fn unpack(word: usize) -> Self {
+        Self {
+            head: (word >> 1) & COUNT_MASK,
+            tail: (word >> (COUNT_BITS + 1)) & COUNT_MASK,
+            reading: word & 1 == 1,
+        }
+    }
+}
+
+/// A single-producer single-consumer
+/// ring buffer. `head` and `tail` count
+/// modulo `period`, a multiple of the
+/// capacity, so that a full and an empty
+/// ring can be told apart without a spare
+/// slot.
+///
+/// The consumer marks the ring as `reading`
+/// before it reads the queued values, and
+/// moves `head` past them as it clears the
+/// mark. On overflow the producer moves
+/// `head` on, or `tail` back over the newest
+/// frame, before it writes, and only while
+/// the ring is not being read.
+struct Ring<T: 'static> {
+    slots: Slots<T>,
+    period: usize,
+    state: AtomicUsize,
+    channels: AtomicUsize,
+    overflows: AtomicUsize,
+    underruns: AtomicUsize,
+}
+
+// Slots outside `head..tail` are only written by the single
+// `StreamProducer`, before it publishes them by moving `tail`.
+// Slots inside are only read by the single `StreamConsumer`,
+// while it has the ring marked as `reading`, which keeps the
+// producer from moving `head` or `tail` over them. So a slot is
+// never read and written at the same time.
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T: Copy> Ring<T> {
+    fn new(slots: Slots<T>) -> Self {
+        assert!(!slots.is_empty(), "a stream needs at least one slot");
+        let counts = 1 << COUNT_BITS;
+        assert!(
+            slots.len() <= counts / 2,
+            "a stream holds at most {} values",
+            counts / 2
+        );
+        let period = slots.len() * (counts / slots.len());
+        Self {
+            slots,
+            period,
+            state: AtomicUsize::new(0),
+            channels: AtomicUsize::new(1),
+            overflows: AtomicUsize::new(0),
+            underruns: AtomicUsize::new(0),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn channels(&self) -> usize {
+        self.channels.load(Ordering::Relaxed)
+    }
+
+    fn len(&self) -> usize {
+        let state = self.state();
+        self.distance(state.head, state.tail)
+    }
+
+    fn state(&self) -> State {
+        State::unpack(self.state.load(Ordering::Acquire))
+    }
+
+    /// Change the state from `current` to `next`,
+    /// handing back the state it had instead if
+    /// the other half changed it in the meantime.
+    fn update(&self, current: State, next: State) -> Result<(), State> {
+        self.state
+            .compare_exchange(
+                current.pack(),
+                next.pack(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map(|_| ())
+            .map_err(State::unpack)
+    }
+
+    /// Move `tail` to `tail`, queueing the
+    /// values the producer wrote before it.
+    fn publish(&self, tail: usize) {
+        let mut state = self.state();
+        while let Err(current) = self.update(state, State { tail, ..state }) {
+            state = current;
+        }
+    }
+
+    fn distance(&self, head: usize, tail: usize) -> usize {
+        (tail + self.period - head) % self.period
+    }
+
+    fn advance(&self, index: usize, count: usize) -> usize {
+        (index + count) % self.period
+    }
+
+    fn retreat(&self, index: usize, count: usize) -> usize {
+        (index + self.period - count) % self.period
+    }
+
+    /// Write `values` from `index` on,
+    /// returning the index after them.
+    fn write(&self, mut index: usize, values: &[T]) -> usize {
+        for value in values {
+            self.slot(index).set(*value);
+            index = self.advance(index, 1);
+        }
+        index
+    }
+
+    fn slot(&self, index: usize) -> &Cell<T> {
+        &self.slots[index % self.capacity()]
+    }
+}
+
+/// The writing half of a stream.
+pub struct StreamProducer<T: 'static> {
+    ring: Arc<Ring<T>>,
+    overflow: Overflow,
+}
+
+/// The reading half of a stream.
+pub struct StreamConsumer<T: 'static> {
+    ring: Arc<Ring<T>>,
+    underflow: Underflow,
+    held: [Option<T>; MAX_CHANNELS],
+    channel: usize,
+}
+
+impl<T: Copy> StreamProducer<T> {
+    /// Push a value, handing it back
+    /// if the stream is full.
+    pub fn enqueue(&mut self, value: T) -> Result<(), T> {
+        let ring = &self.ring;
+        let state = ring.state();
+
+        if ring.distance(state.head, state.tail) == ring.capacity() {
+            return Err(value);
+        }
+
+        ring.publish(ring.write(state.tail, &[value]));
+        Ok(())
+    }
+
+    /// Push a value, following the overflow
+    /// policy if the stream is full. This
+    /// never fails, so it is safe to call
+    /// from the audio thread.
+    pub fn push(&mut self, value: T) {
+        self.push_frame(&[value]);
+    }
+
+    /// Push the values of one frame, one per
+    /// channel, all at once so that readers of
+    /// whole frames never see part of it. The
+    /// overflow policy applies to whole frames.
+    pub fn push_frame(&mut self, frame: &[T]) {
+        let ring = &self.ring;
+        let mut state = ring.state();
+
+        loop {
+            let queued = ring.distance(state.head, state.tail);
+            if frame.len() <= ring.capacity() - queued {
+                ring.publish(ring.write(state.tail, frame));
+                return;
+            }
+
+            let fits = frame.len() <= ring.capacity();
+            if self.overflow == Overflow::DropNewest || !fits || state.reading {
+                ring.overflows.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+
+            // Take the newest frame, or the oldest whole
+            // frames, out of the queue, then write the
+            // frame where they were on the next round.
+            let next = match self.overflow {
+                Overflow::Overwrite if queued >= frame.len() => State {
+                    tail: ring.retreat(state.tail, frame.len()),
+                    ..state
+                },
+                _ => {
+                    let missing = queued + frame.len() - ring.capacity();
+                    let dropped = (missing.div_ceil(frame.len()) * frame.len()).min(queued);
+                    State {
+                        head: ring.advance(state.head, dropped),
+                        ..state
+                    }
+                }
+            };
+
+            match ring.update(state, next) {
+                Ok(()) => {
+                    ring.overflows.fetch_add(1, Ordering::Relaxed);
+                    state = next;
+                }
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Push every value of `values`, following
+    /// the overflow policy for those that do not
+    /// fit. Returns how many of them fitted.
+    pub fn write_from(&mut self, values: &[T]) -> usize {
+        let ring = &self.ring;
+        let state = ring.state();
+        let written = values
+            .len()
+            .min(ring.capacity() - ring.distance(state.head, state.tail));
+
+        ring.publish(ring.write(state.tail, &values[..written]));
+
+        for value in &values[written..] {
+            self.push(*value);
+        }
+        written
+    }
+
+    /// Set how many channels the frames of the
+    /// stream interleave, for its readers.
+    pub fn set_channels(&mut self, channels: usize) {
+        let channels = channels.clamp(1, MAX_CHANNELS);
+        self.ring.channels.store(channels, Ordering::Relaxed);
+    }
+
+    /// Whether `consumer` reads
+    /// what this producer writes.
+    pub(crate) fn feeds(&self, consumer: &StreamConsumer<T>) -> bool {
+        Arc::ptr_eq(&self.ring, &consumer.ring)
+    }
+
+    /// Set what `push` does
+    /// when the stream is full.
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// The number of channels
+    /// the frames interleave.
+    pub fn channels(&self) -> usize {
+        self.ring.channels()
+    }
+
+    /// The number of frames pushed
+    /// into the stream while it was full.
+    pub fn overflows(&self) -> usize {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+
+    /// The number of values popped
+    /// from the stream while it was empty.
+    pub fn underruns(&self) -> usize {
+        self.ring.underruns.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Copy> StreamConsumer<T> {
+    /// Pop the oldest value, if any.
+    pub fn dequeue(&mut self) -> Option<T> {
+        let mut value = None;
+        self.read_with(1, |_, read| value = Some(read));
+        value
+    }
+
+    /// Read up to `count` values into `write`,
+    /// stopping at the end of the last whole
+    /// frame. Returns how many were read. This
+    /// never waits for the producer: it only
+    /// tries again if the producer changed the
+    /// queue while it was looking at it.
+    fn read_with(&mut self, count: usize, mut write: impl FnMut(usize, T)) -> usize {
+        let ring = &self.ring;
+        let channels = ring.channels();
+
+        let mut state = ring.state();
+        let read = loop {
+            let queued = ring.distance(state.head, state.tail);
+            let whole_frames = queued - (self.channel + queued) % channels;
+            let read = count.min(whole_frames);
+            if read == 0 {
+                return 0;
+            }
+
+            let reading = State {
+                reading: true,
+                ..state
+            };
+            match ring.update(state, reading) {
+                Ok(()) => break read,
+                Err(current) => state = current,
+            }
+        };
+
+        for index in 0..read {
+            let value = ring.slot(ring.advance(state.head, index)).get();
+            self.held[self.channel % MAX_CHANNELS] = Some(value);
+            self.channel = (self.channel + 1) % channels;
+            write(index, value);
+        }
+
+        let mut reading = State {
+            reading: true,
+            ..state
+        };
+        loop {
+            let done = State {
+                head: ring.advance(reading.head, read),
+                reading: false,
+                ..reading
+            };
+            match ring.update(reading, done) {
+                Ok(()) => return read,
+                Err(current) => reading = current,
+            }
+        }
+    }
+
+    /// Set what `pop` reads
+    /// when the stream is empty.
+    pub fn with_underflow(mut self, underflow: Underflow) -> Self {
+        self.underflow = underflow;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// The number of channels
+    /// the frames interleave.
+    pub fn channels(&self) -> usize {
+        self.ring.channels()
+    }
+
+    /// The number of frames pushed
+    /// into the stream while it was full.
+    pub fn overflows(&self) -> usize {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+
+    /// The number of values popped
+    /// from the stream while it was empty.
+    pub fn underruns(&self) -> usize {
+        self.ring.underruns.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Copy + Default> StreamConsumer<T> {
+    /// Pop the oldest value, following the
+    /// underflow policy if the stream is empty.
+    pub fn pop(&mut self) -> T {
+        if let Some(value) = self.dequeue() {
+            return value;
+        }
+
+        let mut value = T::default();
+        self.underflow_into(1, |_, underflow| value = underflow);
+        value
+    }
+
+    /// Fill `buffer` with the oldest values,
+    /// following the underflow policy for the
+    /// rest if there are not enough of them.
+    /// Returns how many values were read.
+    pub fn read_into(&mut self, buffer: &mut [T]) -> usize {
+        let read = self.read_with(buffer.len(), |index, value| buffer[index] = value);
+        self.underflow_into(buffer.len() - read, |index, value| {
+            buffer[read + index] = value
+        });
+        read
+    }
+
+    /// Fill `buffer` with whole frames, their
+    /// channels interleaved. Returns how many
+    /// frames were read.
+    pub fn read_interleaved(&mut self, buffer: &mut [T]) -> usize {
+        self.read_into(buffer) / self.channels()
+    }
+
+    /// Fill one buffer per channel with whole
+    /// frames, as many as the shortest buffer
+    /// holds. Returns how many frames were read.
+    pub fn read_planar(&mut self, buffers: &mut [&mut [T]]) -> usize {
+        let channels = self.channels();
+        let frames = buffers.iter().map(|buffer| buffer.len()).min().unwrap_or(0);
+        let mut write = |index: usize, value: T| {
+            if let Some(buffer) = buffers.get_mut(index % channels) {
+                buffer[index / channels] = value;
+            }
+        };
+
+        let read = self.read_with(frames * channels, &mut write);
+        self.underflow_into(frames * channels - read, |index, value| {
+            write(read + index, value)
+        });
+        read / channels
+    }
+
+    /// Write `count` values following the underflow
+    /// policy, counting each of them as an underrun.
+    fn underflow_into(&mut self, count: usize, mut write: impl FnMut(usize, T)) {
+        if count > 0 {
+            self.ring.underruns.fetch_add(count, Ordering::Relaxed);
+        }
+
+        for index in 0..count {
+            write(index, self.underflow_value());
+            self.channel = (self.channel + 1) % self.channels();
+        }
+    }
+
+    fn underflow_value(&self) -> T {
+        match self.underflow {
+            Underflow::Hold => self.held[self.channel % MAX_CHANNELS].unwrap_or_default(),
+            Underflow::Silence => T::default(),
+        }
+    }
+}
+
+/// Create a stream holding up to
+/// `capacity` values, allocated on
+/// the heap and freed once both
+/// halves are dropped.
+///
+/// ```
+///     use rume_core::stream;
+///
+///     let (mut producer, mut consumer) = stream::<f32>(2);
+///
+///     producer.enqueue(1.0).unwrap();
+///     producer.enqueue(2.0).unwrap();
+///     assert_eq!(producer.enqueue(3.0), Err(3.0));
+///
+///     assert_eq!(consumer.dequeue(), Some(1.0));
+///     assert_eq!(consumer.dequeue(), Some(2.0));
+///     assert_eq!(consumer.dequeue(), None);
+/// ```
+pub fn stream<T: Copy + Default + Send>(capacity: usize) -> (StreamProducer<T>, StreamConsumer<T>) {
+    split(Slots::Heap(vec![Cell::new(T::default()); capacity].into()))
+}
+
+/// Create a stream over a buffer
+/// supplied by the caller, for targets
+/// that keep their queues in static
+/// memory. The stream holds as many
+/// values as the buffer is long.
+///
+/// ```
+///     use rume_core::stream_from;
+///
+///     static mut BUFFER: [f32; 4] = [0.0; 4];
+///
+///     let buffer = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+///     let (mut producer, mut consumer) = stream_from(buffer);
+///
+///     producer.enqueue(3.14).unwrap();
+///     assert_eq!(consumer.dequeue(), Some(3.14));
+///     assert_eq!(consumer.capacity(), 4);
+/// ```
+pub fn stream_from<T: Copy + Send>(
+    buffer: &'static mut [T],
+) -> (StreamProducer<T>, StreamConsumer<T>) {
+    split(Slots::Borrowed(Cell::from_mut(buffer).as_slice_of_cells()))
+}
+
+fn split<T: Copy + Send>(slots: Slots<T>) -> (StreamProducer<T>, StreamConsumer<T>) {
+    let ring = Arc::new(Ring::new(slots));
+    (
+        StreamProducer {
+            ring: ring.clone(),
+            overflow: Overflow::default(),
+        },
+        StreamConsumer {
+            ring,
+            underflow: Underflow::default(),
+            held: [None; MAX_CHANNELS],
+            channel: 0,
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// How many values the tests across threads
+    /// send, few enough to run them under Miri.
+    const THREADED_COUNT: usize = if cfg!(miri) { 200 } else { 10_000 };
+
+    #[test]
+    fn values_come_out_in_order() {
+        let (mut producer, mut consumer) = stream::<f32>(3);
+
+        for round in 0..10 {
+            let value = round as f32;
+            producer.enqueue(value).unwrap();
+            producer.enqueue(value + 0.5).unwrap();
+            assert_eq!(consumer.len(), 2);
+            assert_eq!(consumer.dequeue(), Some(value));
+            assert_eq!(consumer.dequeue(), Some(value + 0.5));
+            assert!(consumer.is_empty());
+        }
+    }
+
+    #[test]
+    fn full_stream_rejects_values() {
+        let (mut producer, mut consumer) = stream::<i32>(2);
+
+        assert_eq!(producer.enqueue(1), Ok(()));
+        assert_eq!(producer.enqueue(2), Ok(()));
+        assert_eq!(producer.enqueue(3), Err(3));
+        assert_eq!(producer.len(), producer.capacity());
+
+        assert_eq!(consumer.dequeue(), Some(1));
+        assert_eq!(producer.enqueue(3), Ok(()));
+        assert_eq!(consumer.dequeue(), Some(2));
+        assert_eq!(consumer.dequeue(), Some(3));
+        assert_eq!(consumer.dequeue(), None);
+    }
+
+    #[test]
+    fn overflow_policies_choose_the_value_to_lose() {
+        let fill = |overflow| {
+            let (producer, mut consumer) = stream::<i32>(3);
+            let mut producer = producer.with_overflow(overflow);
+            (1..=5).for_each(|value| producer.push(value));
+
+            assert_eq!(producer.overflows(), 2);
+            core::iter::from_fn(|| consumer.dequeue()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(fill(Overflow::DropNewest), [1, 2, 3]);
+        assert_eq!(fill(Overflow::DropOldest), [3, 4, 5]);
+        assert_eq!(fill(Overflow::Overwrite), [1, 2, 5]);
+    }
+
+    #[test]
+    fn underflow_policies_choose_the_value_to_read() {
+        let (mut producer, consumer) = stream::<f32>(4);
+        let mut consumer = consumer.with_underflow(Underflow::Hold);
+
+        assert_eq!(consumer.pop(), 0.0);
+        producer.push(0.5);
+        assert_eq!(consumer.pop(), 0.5);
+        assert_eq!(consumer.pop(), 0.5);
+        assert_eq!(consumer.underruns(), 2);
+
+        let (mut producer, mut consumer) = stream::<f32>(4);
+        producer.push(0.5);
+        assert_eq!(consumer.pop(), 0.5);
+        assert_eq!(consumer.pop(), 0.0);
+        assert_eq!(producer.underruns(), 1);
+    }
+
+    #[test]
+    fn blocks_are_written_and_read_in_one_go() {
+        let (mut producer, consumer) = stream::<f32>(4);
+        let mut consumer = consumer.with_underflow(Underflow::Hold);
+
+        assert_eq!(producer.write_from(&[1.0, 2.0, 3.0, 4.0, 5.0]), 4);
+        assert_eq!(producer.overflows(), 1);
+
+        let mut buffer = [0.0; 3];
+        assert_eq!(consumer.read_into(&mut buffer), 3);
+        assert_eq!(buffer, [1.0, 2.0, 3.0]);
+
+        assert_eq!(consumer.read_into(&mut buffer), 1);
+        assert_eq!(buffer, [4.0, 4.0, 4.0]);
+        assert_eq!(consumer.underruns(), 2);
+    }
+
+    #[test]
+    fn frames_are_kept_whole() {
+        let (producer, mut consumer) = stream::<i32>(5);
+        let mut producer = producer.with_overflow(Overflow::DropOldest);
+        producer.set_channels(2);
+
+        producer.push_frame(&[1, -1]);
+        producer.push_frame(&[2, -2]);
+        producer.push_frame(&[3, -3]);
+        assert_eq!(producer.overflows(), 1);
+
+        let mut left = [0; 3];
+        let mut right = [0; 3];
+        assert_eq!(consumer.read_planar(&mut [&mut left, &mut right]), 2);
+        assert_eq!(left, [2, 3, 0]);
+        assert_eq!(right, [-2, -3, 0]);
+    }
+
+    #[test]
+    fn partial_frames_are_not_read() {
+        let (mut producer, consumer) = stream::<f32>(8);
+        let mut consumer = consumer.with_underflow(Underflow::Hold);
+        producer.set_channels(2);
+
+        producer.write_from(&[0.1, -0.1, 0.2]);
+        let mut buffer = [0.0; 4];
+        assert_eq!(consumer.read_interleaved(&mut buffer), 1);
+        assert_eq!(buffer, [0.1, -0.1, 0.1, -0.1]);
+
+        producer.push(-0.2);
+        assert_eq!(consumer.read_interleaved(&mut buffer), 1);
+        assert_eq!(buffer[..2], [0.2, -0.2]);
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let (mut first_producer, mut first_consumer) = stream::<f32>(4);
+        let (mut second_producer, mut second_consumer) = stream::<f32>(4);
+
+        first_producer.enqueue(1.0).unwrap();
+        second_producer.enqueue(2.0).unwrap();
+
+        assert_eq!(first_consumer.dequeue(), Some(1.0));
+        assert_eq!(first_consumer.dequeue(), None);
+        assert_eq!(second_consumer.dequeue(), Some(2.0));
+        assert_eq!(second_consumer.dequeue(), None);
+    }
+
+    #[test]
+    fn values_being_read_are_left_to_the_consumer() {
+        for overflow in [Overflow::DropOldest, Overflow::Overwrite].iter() {
+            let (producer, mut consumer) = stream::<i32>(2);
+            let mut producer = producer.with_overflow(*overflow);
+            producer.push(1);
+            producer.push(2);
+
+            let ring = &consumer.ring;
+            let state = ring.state();
+            let reading = State {
+                reading: true,
+                ..state
+            };
+            ring.update(state, reading).unwrap();
+            producer.push(3);
+            ring.update(reading, state).unwrap();
+
+            assert_eq!(producer.overflows(), 1);
+            assert_eq!(consumer.dequeue(), Some(1));
+            assert_eq!(consumer.dequeue(), Some(2));
+            assert_eq!(consumer.dequeue(), None);
+        }
+    }
+
+    #[test]
+    fn dropping_oldest_keeps_values_in_order_across_threads() {
+        const COUNT: usize = THREADED_COUNT;
+        let (producer, mut consumer) = stream::<usize>(8);
+        let mut producer = producer.with_overflow(Overflow::DropOldest);
+
+        let writer = std::thread::spawn(move || {
+            (0..COUNT).for_each(|value| producer.push(value));
+            producer
+        });
+
+        let mut received = 0;
+        let mut previous = None;
+        while !writer.is_finished() || !consumer.is_empty() {
+            if let Some(value) = consumer.dequeue() {
+                assert!(previous < Some(value));
+                previous = Some(value);
+                received += 1;
+            }
+        }
+
+        let producer = writer.join().unwrap();
+        assert_eq!(received + producer.overflows(), COUNT);
+    }
+
+    #[test]
+    fn overwriting_never_hides_queued_values_from_the_consumer() {
+        const CAPACITY: usize = THREADED_COUNT / 2;
+        let (producer, mut consumer) = stream::<usize>(CAPACITY);
+        let mut producer = producer.with_overflow(Overflow::Overwrite);
+        (0..CAPACITY).for_each(|value| producer.push(value));
+
+        let done = std::sync::Arc::new(core::sync::atomic::AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let done = done.clone();
+            move || {
+                let mut value = CAPACITY;
+                while !done.load(Ordering::Relaxed) {
+                    producer.push(value);
+                    value += 1;
+                }
+            }
+        });
+
+        for expected in 0..CAPACITY - 1 {
+            assert_eq!(consumer.dequeue(), Some(expected));
+        }
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+        assert_eq!(consumer.underruns(), 0);
+    }
+
+    #[test]
+    fn halves_can_live_on_different_threads() {
+        const COUNT: usize = THREADED_COUNT;
+        let (mut producer, mut consumer) = stream::<usize>(16);
+
+        let writer = std::thread::spawn(move || {
+            for value in 0..COUNT {
+                while producer.enqueue(value).is_err() {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < COUNT {
+            match consumer.dequeue() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => std::thread::yield_now(),
+            }
+        }
+
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    graph::{chain::*, error::*, io::*, node::*, proc::*},
+    lib::*,
+};
+
+/// A `SignalChain` packaged as a processor,
+/// so that it can be added to another chain
+/// as many times as needed and connected
+/// like any other processor.
+///
+/// Each of its input ports feeds an `Inlet`
+/// in the chain, and each of its output
+/// ports is fed by an `Outlet`, in the
+/// order they were added to the
+/// `SubgraphBuilder`.
+///
+/// ```
+///     use rume_core::*;
+///
+///     #[derive(Default)]
+///     pub struct Double {
+///         pub input: DoubleInput,
+///         pub output: DoubleOutput,
+///         value: f32,
+///     }
+///
+///     impl Processor for Double {
+///         fn prepare(&mut self, _: AudioConfig) {}
+///         fn process(&mut self) { self.value *= 2.0; }
+///     }
+///
+///     input! { Double, DoubleInput, |proc: &mut Double, value: f32| { proc.value = value; } }
+///     output! { Double, DoubleOutput, |proc: &mut Double| -> f32 { proc.value } }
+///
+///     fn voice() -> Subgraph {
+///         let mut builder = SubgraphBuilder::default();
+///         let (inlet, outlet) = (builder.input(), builder.output());
+///         let double = builder.add(Double::default());
+///         connect!(builder, (inlet) => (double));
+///         connect!(builder, (double) => (outlet));
+///         builder.build()
+///     }
+///
+///     let mut builder = SignalChainBuilder::default();
+///     let (a, b) = (builder.add(voice()), builder.add(voice()));
+///     connect!(builder, (a, 0) => (b, 0));
+///     let mut chain = builder.build();
+///
+///     chain.prepare(48_000.into());
+///     chain.render(64);
+/// ```
+pub struct Subgraph {
+    pub input: SubgraphInputs,
+    pub output: SubgraphOutputs,
+    chain: SignalChain,
+    inlets: Vec<Handle<Inlet>>,
+    outlets: Vec<Handle<Outlet>>,
+}
+
+impl Processor for Subgraph {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.chain.prepare(config);
+    }
+
+    /// A subgraph only renders a
+    /// block at a time.
+    fn process(&mut self) {}
+
+    fn as_block_processor(&mut self) -> Option<&mut dyn BlockProcessor> {
+        Some(self)
+    }
+}
+
+impl BlockProcessor for Subgraph {
+    fn num_inputs(&self) -> usize {
+        self.inlets.len()
+    }
+
+    fn num_outputs(&self) -> usize {
+        self.outlets.len()
+    }
+
+    fn process_block(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        let num_samples = match (inputs.first(), outputs.first()) {
+            (Some(input), _) => input.len(),
+            (_, Some(output)) => output.len(),
+            _ => return,
+        };
+
+        for (inlet, input) in self.inlets.iter().zip(inputs) {
+            if let Some(inlet) = self.chain.processor_mut(*inlet) {
+                inlet.fill(input);
+            }
+        }
+
+        self.chain.render(num_samples);
+
+        for (outlet, output) in self.outlets.iter().zip(outputs.iter_mut()) {
+            if let Some(outlet) = self.chain.processor_mut(*outlet) {
+                outlet.drain(output);
+            }
+        }
+    }
+}
+
+/// Builds a `Subgraph` the same way a
+/// `SignalChainBuilder` builds a chain,
+/// with `Inlet`s and `Outlet`s marking
+/// its boundary ports.
+#[derive(Default)]
+pub struct SubgraphBuilder {
+    builder: SignalChainBuilder,
+    inlets: Vec<Handle<Inlet>>,
+    outlets: Vec<Handle<Outlet>>,
+}
+
+impl SubgraphBuilder {
+    pub fn add<P>(&mut self, processor: P) -> Handle<P>
+    where
+        P: Processor + Send + 'static,
+    {
+        self.builder.add(processor)
+    }
+
+    /// Add the next input port of the subgraph. What
+    /// is connected to it in the parent chain comes
+    /// out of the returned inlet.
+    pub fn input(&mut self) -> Handle<Inlet> {
+        let inlet = self.builder.add(Inlet::default());
+        self.inlets.push(inlet);
+        inlet
+    }
+
+    /// Add the next output port of the subgraph.
+    /// What is connected to the returned outlet
+    /// comes out of it in the parent chain.
+    pub fn output(&mut self) -> Handle<Outlet> {
+        let outlet = self.builder.add(Outlet::default());
+        self.outlets.push(outlet);
+        outlet
+    }
+
+    pub fn processor_mut<P: 'static>(&mut self, handle: Handle<P>) -> Option<&mut P> {
+        self.builder.processor_mut(handle)
+    }
+
+    pub fn connection(mut self, output: OutputPort, input: InputPort) -> Self {
+        self.builder = self.builder.connection(output, input);
+        self
+    }
+
+    pub fn try_connection(
+        mut self,
+        output: OutputPort,
+        input: InputPort,
+    ) -> Result<Self, GraphError> {
+        self.builder = self.builder.try_connection(output, input)?;
+        Ok(self)
+    }
+
+    /// Connect an output back to an input upstream
+    /// of it, closing a loop. The input receives
+    /// the data one block after it was produced.
+    pub fn feedback(mut self, output: OutputPort, input: InputPort) -> Self {
+        self.builder = self.builder.feedback(output, input);
+        self
+    }
+
+    pub fn try_feedback(
+        mut self,
+        output: OutputPort,
+        input: InputPort,
+    ) -> Result<Self, GraphError> {
+        self.builder = self.builder.try_feedback(output, input)?;
+        Ok(self)
+    }
+
+    /// Choose how an input port of a processor
+    /// combines the data of several connections,
+    /// overriding the processor's own choice.
+    pub fn merge<P>(mut self, processor: Handle<P>, input: usize, merge: Merge) -> Self {
+        self.builder = self.builder.merge(processor, input, merge);
+        self
+    }
+
+    pub fn build(self) -> Subgraph {
+        match self.try_build() {
+            Ok(subgraph) => subgraph,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_build(self) -> Result<Subgraph, GraphError> {
+        let num_ports = self.inlets.len().max(self.outlets.len());
+        if num_ports > max_block_ports() {
+            return Err(GraphError::TooManyPorts(num_ports));
+        }
+
+        Ok(Subgraph {
+            input: SubgraphInputs::default(),
+            output: SubgraphOutputs::default(),
+            chain: self.builder.try_build()?,
+            inlets: self.inlets,
+            outlets: self.outlets,
+        })
+    }
+}
+
+/// The processor behind an input port of a
+/// `Subgraph`. It outputs, a sample at a time,
+/// the block the subgraph received on that port.
+#[derive(Default)]
+pub struct Inlet {
+    pub input: (),
+    pub output: InletOutput,
+    block: Vec<f32>,
+    position: usize,
+    value: f32,
+}
+
+impl Inlet {
+    fn fill(&mut self, input: &[f32]) {
+        let num_samples = input.len().min(self.block.len());
+        self.block[..num_samples].copy_from_slice(&input[..num_samples]);
+        self.position = 0;
+    }
+}
+
+impl Processor for Inlet {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.block.resize(config.buffer_size, 0.0);
+    }
+
+    fn process(&mut self) {
+        self.value = self.block.get(self.position).copied().unwrap_or_default();
+        self.position += 1;
+    }
+}
+
+output! { Inlet, InletOutput, |proc: &mut Inlet| -> f32 { proc.value } }
+
+/// The processor behind an output port of a
+/// `Subgraph`. It collects, a sample at a time,
+/// the block the subgraph sends out of that port.
+#[derive(Default)]
+pub struct Outlet {
+    pub input: OutletInput,
+    pub output: (),
+    block: Vec<f32>,
+    position: usize,
+    value: f32,
+}
+
+impl Outlet {
+    fn drain(&mut self, output: &mut [f32]) {
+        let num_samples = output.len().min(self.position);
+        output[..num_samples].copy_from_slice(&self.block[..num_samples]);
+        self.position = 0;
+    }
+}
+
+impl Processor for Outlet {
+    fn prepare(&mut self, config: AudioConfig) {
+        self.block.resize(config.buffer_size, 0.0);
+    }
+
+    fn process(&mut self) {
+        if let Some(sample) = self.block.get_mut(self.position) {
+            *sample = self.value;
+            self.position += 1;
+        }
+    }
+}
+
+input! { Outlet, OutletInput, |proc: &mut Outlet, value: f32| { proc.value = value; } }
+
+/// An input port of a `Subgraph`. The subgraph
+/// renders whole blocks, so it is never set a
+/// sample at a time.
+#[derive(Debug, Default, Clone)]
+pub struct SubgraphInput;
+
+impl Input<Subgraph> for SubgraphInput {
+    fn set(&self, _: &mut Subgraph, _: f32) {}
+}
+
+/// An output port of a `Subgraph`. The subgraph
+/// renders whole blocks, so it is never read a
+/// sample at a time.
+#[derive(Debug, Default, Clone)]
+pub struct SubgraphOutput;
+
+impl Output<Subgraph> for SubgraphOutput {
+    fn get(&self, _: &mut Subgraph) -> f32 {
+        0.0
+    }
+}
+
+/// The input ports of a `Subgraph`, one field
+/// per port a block processor can have, so that
+/// they are reached as `input.0`, `input.1`...
+/// The ports a subgraph declares come first.
+#[derive(Debug, Default, Clone)]
+#[rustfmt::skip]
+pub struct SubgraphInputs(
+    pub SubgraphInput, pub SubgraphInput, pub SubgraphInput, pub SubgraphInput,
+    pub SubgraphInput, pub SubgraphInput, pub SubgraphInput, pub SubgraphInput,
+    pub SubgraphInput, pub SubgraphInput, pub SubgraphInput, pub SubgraphInput,
+    pub SubgraphInput, pub SubgraphInput, pub SubgraphInput, pub SubgraphInput,
+);
+
+impl Input<Subgraph> for SubgraphInputs {
+    fn set(&self, _: &mut Subgraph, _: f32) {}
+}
+
+/// The output ports of a `Subgraph`, one field
+/// per port a block processor can have, so that
+/// they are reached as `output.0`, `output.1`...
+/// The ports a subgraph declares come first.
+#[derive(Debug, Default, Clone)]
+#[rustfmt::skip]
+pub struct SubgraphOutputs(
+    pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput,
+    pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput,
+    pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput,
+    pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput, pub SubgraphOutput,
+);
+
+impl Output<Subgraph> for SubgraphOutputs {
+    fn get(&self, _: &mut Subgraph) -> f32 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proc::dummies::*;
+
+    fn pass_through() -> Subgraph {
+        let mut builder = SubgraphBuilder::default();
+        let (inlet, outlet) = (builder.input(), builder.output());
+        let dummy = builder.add(DummyProcessor::default());
+
+        connect!(builder, (inlet) => (dummy));
+        connect!(builder, (dummy) => (outlet));
+        builder.build()
+    }
+
+    fn sum_of_two() -> Subgraph {
+        let mut builder = SubgraphBuilder::default();
+        let (a, b) = (builder.input(), builder.input());
+        let (sum, a_out) = (builder.output(), builder.output());
+
+        connect!(builder, (a) => (sum));
+        connect!(builder, (b) => (sum));
+        connect!(builder, (a) => (a_out));
+        builder.build()
+    }
+
+    #[test]
+    fn data_is_passed_through_a_subgraph() {
+        const VALUE_TO_PASS: f32 = 1.5;
+
+        let mut builder = SignalChainBuilder::default();
+        let (input, output) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let (first, second) = (builder.add(pass_through()), builder.add(pass_through()));
+
+        let mut chain = chain! { builder,
+            (input) => (first),
+                       (first) => (second),
+                                  (second) => (output)
+        };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, input, VALUE_TO_PASS);
+        chain.render(3);
+
+        assert_eq!(value(&mut chain, output), VALUE_TO_PASS);
+    }
+
+    #[test]
+    fn subgraph_ports_are_reached_by_index() {
+        let mut builder = SignalChainBuilder::default();
+        let (a, b, sum, a_out) = (
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+            builder.add(DummyProcessor::default()),
+        );
+        let subgraph = builder.add(sum_of_two());
+
+        let mut chain = chain! { builder,
+            (a) => (subgraph, 0),
+            (b) => (subgraph, 1),
+                   (subgraph, 0) => (sum),
+                   (subgraph, 1) => (a_out)
+        };
+
+        chain.prepare(48_000.into());
+        set_value(&mut chain, a, 2.0);
+        set_value(&mut chain, b, 3.0);
+        chain.render(1);
+
+        assert_eq!(value(&mut chain, sum), 5.0);
+        assert_eq!(value(&mut chain, a_out), 2.0);
+    }
+
+    #[test]
+    fn connecting_a_port_a_subgraph_does_not_declare_fails() {
+        let mut builder = SignalChainBuilder::default();
+        let a = builder.add(DummyProcessor::default());
+        let subgraph = builder.add(pass_through());
+
+        let result = builder.try_connection(make_output_port!(a), make_input_port!(subgraph, 1));
+
+        assert_eq!(
+            result.err(),
+            Some(GraphError::PortOutOfRange {
+                processor: 1,
+                port: 1
+            })
+        );
+    }
+
+    #[test]
+    fn subgraphs_can_be_sent_to_another_thread() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Subgraph>();
+    }
+}
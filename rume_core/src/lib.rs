@@ -17,6 +17,7 @@ pub mod lib {
     pub use std::{
         boxed::Box,
         rc::{Rc, Weak},
+        sync::Arc,
         vec,
         vec::Vec,
     };
@@ -27,6 +28,7 @@ pub mod lib {
     pub use alloc::{
         boxed::Box,
         rc::{Rc, Weak},
+        sync::Arc,
         vec,
         vec::Vec,
     };
@@ -48,6 +50,9 @@ pub trait F32Extension {
     fn log2(self) -> f32;
     fn log10(self) -> f32;
     fn sin(self) -> f32;
+    fn cos(self) -> f32;
+    fn tan(self) -> f32;
+    fn sqrt(self) -> f32;
     fn exp(self) -> f32;
 }
 
@@ -73,6 +78,21 @@ impl F32Extension for f32 {
         libm::sinf(self)
     }
 
+    #[inline(always)]
+    fn cos(self) -> f32 {
+        libm::cosf(self)
+    }
+
+    #[inline(always)]
+    fn tan(self) -> f32 {
+        libm::tanf(self)
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> f32 {
+        libm::sqrtf(self)
+    }
+
     #[inline(always)]
     fn exp(self) -> f32 {
         libm::expf(self)
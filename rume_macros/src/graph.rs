@@ -106,7 +106,7 @@ impl ProcessorsDecl {
 
         for decl in &self.decls {
             processor_init.push_str(&format!(
-                "\tlet {} = rume::make_processor({});\n",
+                "\tlet {} = builder.add({});\n",
                 decl.name, decl.initialiser
             ));
         }
@@ -146,11 +146,60 @@ struct ConnectionDecl {
     rx_port: String,
 }
 
+/// The path and index of a port. The index is
+/// the number in its path, e.g. `.input.1` is
+/// port `1`. Ports in nested tuples give theirs
+/// explicitly, e.g. `.input.1.0[2]`, as the
+/// macro cannot tell how the tuples flatten.
+fn port_path_and_index(port: &str) -> (String, usize) {
+    if let Some(open) = port.find('[') {
+        let index = port[open + 1..]
+            .trim_end_matches(']')
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("Failed to parse the index of port {}", port));
+        return (port[..open].to_string(), index);
+    }
+
+    let numbers: Vec<&str> = port
+        .split('.')
+        .filter(|segment| segment.parse::<usize>().is_ok())
+        .collect();
+    match numbers.as_slice() {
+        [] => (port.to_string(), 0),
+        [index] => (port.to_string(), index.parse().unwrap()),
+        _ => panic!(
+            "Nested port {} needs an explicit index, e.g. {}[2]",
+            port, port
+        ),
+    }
+}
+
 #[derive(Debug, Default)]
 struct ConnectionsDecl {
     decls: Vec<ConnectionDecl>,
 }
 
+impl ConnectionsDecl {
+    pub fn to_connections(&self) -> String {
+        let mut connections = String::new();
+
+        for decl in self.decls.iter() {
+            let (tx_port, tx_index) = port_path_and_index(&decl.tx_port);
+            let (rx_port, rx_index) = port_path_and_index(&decl.rx_port);
+            connections.push_str(&format!(
+                "\t\t.connection(
+                    \trume::OutputPort::of({}, {}, |proc| &proc{}),
+                    \trume::InputPort::of({}, {}, |proc| &proc{})
+                )\n",
+                decl.tx_processor, tx_index, tx_port, decl.rx_processor, rx_index, rx_port,
+            ));
+        }
+
+        connections
+    }
+}
+
 impl ParsableDecl for ConnectionsDecl {
     fn parse(&mut self, tokens: &mut IntoIter) {
         let num_connections = num_chars_in(tokens.by_ref(), '>');
@@ -187,12 +236,147 @@ impl ParsableDecl for ConnectionsDecl {
     }
 }
 
+/// The options that size the stream of an
+/// input or output endpoint, and that choose
+/// what happens when it is full or empty.
+#[derive(Debug)]
+enum StreamOption {
+    Capacity(usize),
+    Sample(String),
+    Overflow(String),
+    Underflow(String),
+}
+
+impl StreamOption {
+    pub fn parse(name: &str, tokens: &mut IntoIter) -> Option<Self> {
+        let mut value = || {
+            tokens
+                .by_ref()
+                .next()
+                .expect("Expected a value after an endpoint stream option")
+                .to_string()
+        };
+
+        match name {
+            "capacity" => Some(StreamOption::Capacity(
+                value()
+                    .parse::<usize>()
+                    .expect("Failed to parse endpoint capacity"),
+            )),
+            "sample" => Some(StreamOption::Sample(value())),
+            "overflow" => Some(StreamOption::Overflow(
+                match value().as_str() {
+                    "drop_newest" => "DropNewest",
+                    "drop_oldest" => "DropOldest",
+                    "overwrite" => "Overwrite",
+                    policy => panic!("Unsupported endpoint overflow policy: {}", policy),
+                }
+                .to_string(),
+            )),
+            "underflow" => Some(StreamOption::Underflow(
+                match value().as_str() {
+                    "silence" => "Silence",
+                    "hold" => "Hold",
+                    policy => panic!("Unsupported endpoint underflow policy: {}", policy),
+                }
+                .to_string(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The stream of an endpoint, as written
+/// by its options or else the defaults.
+struct StreamDecl {
+    capacity: String,
+    sample: String,
+    overflow: Option<String>,
+    underflow: Option<String>,
+}
+
+impl StreamDecl {
+    pub fn new<'a>(options: impl Iterator<Item = &'a StreamOption>, capacity: &str) -> Self {
+        let mut decl = Self {
+            capacity: capacity.to_string(),
+            sample: "rume::StreamDataType".to_string(),
+            overflow: None,
+            underflow: None,
+        };
+
+        for option in options {
+            match option {
+                StreamOption::Capacity(capacity) => decl.capacity = capacity.to_string(),
+                StreamOption::Sample(sample) => decl.sample = sample.clone(),
+                StreamOption::Overflow(policy) => decl.overflow = Some(policy.clone()),
+                StreamOption::Underflow(policy) => decl.underflow = Some(policy.clone()),
+            }
+        }
+
+        decl
+    }
+
+    /// The producer and consumer of the stream
+    /// of the `endpoint` endpoint called `name`.
+    pub fn to_init(&self, name: &str, endpoint: &str) -> String {
+        let mut stream_init = format!(
+            "let ({}_producer, {}_consumer) = rume::make_{}_endpoint_with::<{}>({});",
+            name, name, endpoint, self.sample, self.capacity
+        );
+
+        if let Some(policy) = &self.overflow {
+            stream_init.push_str(&format!(
+                "\n\tlet {}_producer = {}_producer.with_overflow(rume::Overflow::{});",
+                name, name, policy
+            ));
+        }
+
+        if let Some(policy) = &self.underflow {
+            stream_init.push_str(&format!(
+                "\n\tlet {}_consumer = {}_consumer.with_underflow(rume::Underflow::{});",
+                name, name, policy
+            ));
+        }
+
+        stream_init
+    }
+}
+
+/// Parse a number, which is split in two
+/// tokens when it is negative.
+fn parse_number(tokens: &mut IntoIter) -> f32 {
+    let mut token = tokens
+        .by_ref()
+        .next()
+        .expect("Expected a value after an input endpoint option");
+
+    let mut sign = 1.0;
+    if let TokenTree::Punct(punct) = &token {
+        if punct.as_char() == '-' {
+            sign = -1.0;
+            token = tokens
+                .by_ref()
+                .next()
+                .expect("Expected a value after a '-'");
+        }
+    }
+
+    sign * token
+        .to_string()
+        .parse::<f32>()
+        .expect("Failed to parse input endpoint option value")
+}
+
 #[derive(Debug)]
 enum GraphInputOption {
     Kind(InputEndpointKind),
     Range(Range<f32>),
     Init(f32),
-    Smooth(u32),
+    Taper(String),
+    Smooth(f32),
+    Smoothing(String),
+    Events(usize),
+    Stream(StreamOption),
     None,
 }
 
@@ -209,24 +393,43 @@ impl GraphInputOption {
             .next()
             .expect("Expected a value after an input endpoint init options");
 
-        match kind.to_string().as_str() {
-            "follow" => *self = GraphInputOption::Kind(InputEndpointKind::Follow),
-            "trigger" => *self = GraphInputOption::Kind(InputEndpointKind::Trigger),
+        let mut length = || match tokens
+            .by_ref()
+            .next()
+            .expect("Expected a length after an input endpoint pulse kind")
+        {
+            TokenTree::Group(group) => group.stream().to_string(),
+            token => panic!("Expected a parenthesised pulse length, found: {}", token),
+        };
+
+        let kind = match kind.to_string().as_str() {
+            "follow" => InputEndpointKind::Follow,
+            "trigger" => InputEndpointKind::Trigger,
+            "gate" => InputEndpointKind::Gate,
+            "toggle" => InputEndpointKind::Toggle,
+            "latch" => InputEndpointKind::Latch,
+            "sample_and_hold" => InputEndpointKind::SampleAndHold,
+            "pulse" => InputEndpointKind::Pulse(
+                length()
+                    .parse::<u32>()
+                    .expect("Failed to parse input endpoint pulse length"),
+            ),
+            "pulse_ms" => InputEndpointKind::PulseMillis(
+                length()
+                    .parse::<f32>()
+                    .expect("Failed to parse input endpoint pulse length"),
+            ),
             _ => panic!(
                 "Unsupported input endpoint kind: {}",
                 kind.to_string().as_str()
             ),
-        }
+        };
+
+        *self = GraphInputOption::Kind(kind)
     }
 
     pub fn range(&mut self, tokens: &mut IntoIter) {
-        let min_value = tokens
-            .by_ref()
-            .next()
-            .expect("Expected a value after an input endpoint init options")
-            .to_string()
-            .parse::<f32>()
-            .expect("Failed to parse input endpoint init value");
+        let min_value = parse_number(tokens.by_ref());
 
         let _dot = tokens
             .by_ref()
@@ -238,39 +441,76 @@ impl GraphInputOption {
             .next()
             .expect("Expected a value after a '.' after a range's minimum value");
 
-        let max_value = tokens
-            .by_ref()
-            .next()
-            .expect("Expected a value after an input endpoint init options")
-            .to_string()
-            .parse::<f32>()
-            .expect("Failed to parse input endpoint init value");
+        let max_value = parse_number(tokens.by_ref());
 
         *self = GraphInputOption::Range(min_value..max_value)
     }
 
     pub fn init(&mut self, tokens: &mut IntoIter) {
-        let value = tokens
+        let value = parse_number(tokens.by_ref());
+
+        *self = GraphInputOption::Init(value)
+    }
+
+    pub fn taper(&mut self, tokens: &mut IntoIter) {
+        let taper = tokens
             .by_ref()
             .next()
-            .expect("Expected a value after an input endpoint init options")
-            .to_string()
-            .parse::<f32>()
-            .expect("Failed to parse input endpoint init value");
+            .expect("Expected a value after an input endpoint taper option")
+            .to_string();
 
-        *self = GraphInputOption::Init(value)
+        let mut argument = || {
+            tokens
+                .by_ref()
+                .next()
+                .expect("Expected an argument after an input endpoint taper")
+                .to_string()
+        };
+
+        let taper = match taper.as_str() {
+            "linear" => "Linear".to_string(),
+            "log" => "Log".to_string(),
+            "db" => "Decibels".to_string(),
+            "integer" => "Integer".to_string(),
+            "stepped" => format!("Stepped{}", argument()),
+            "custom" => format!("Custom{}", argument()),
+            _ => panic!("Unsupported input endpoint taper: {}", taper),
+        };
+
+        *self = GraphInputOption::Taper(taper)
     }
 
     pub fn smooth(&mut self, tokens: &mut IntoIter) {
-        let value = tokens
+        *self = GraphInputOption::Smooth(parse_number(tokens.by_ref()))
+    }
+
+    pub fn smoothing(&mut self, tokens: &mut IntoIter) {
+        let curve = tokens
+            .by_ref()
+            .next()
+            .expect("Expected a value after an input endpoint smoothing option")
+            .to_string();
+
+        let curve = match curve.as_str() {
+            "linear" => "Linear",
+            "one_pole" => "OnePole",
+            "cosine" => "RaisedCosine",
+            _ => panic!("Unsupported input endpoint smoothing: {}", curve),
+        };
+
+        *self = GraphInputOption::Smoothing(curve.to_string())
+    }
+
+    pub fn events(&mut self, tokens: &mut IntoIter) {
+        let capacity = tokens
             .by_ref()
             .next()
-            .expect("Expected a value after an input endpoint init options")
+            .expect("Expected a capacity after an input endpoint events option")
             .to_string()
-            .parse::<u32>()
-            .expect("Failed to parse input endpoint init value");
+            .parse::<usize>()
+            .expect("Failed to parse input endpoint events capacity");
 
-        *self = GraphInputOption::Smooth(value)
+        *self = GraphInputOption::Events(capacity)
     }
 
     pub fn to_init(&self) -> String {
@@ -280,8 +520,14 @@ impl GraphInputOption {
                 format!(".range({:.32}..{:.32})", range.start, range.end)
             }
             GraphInputOption::Init(init) => format!(".init({:.32})", init),
-            GraphInputOption::Smooth(smooth) => format!(".smooth({:})", smooth),
-            GraphInputOption::None => String::new(),
+            GraphInputOption::Taper(taper) => format!(".taper(rume::Taper::{})", taper),
+            GraphInputOption::Smooth(smooth) => format!(".smooth_millis({:.32})", smooth),
+            GraphInputOption::Smoothing(curve) => {
+                format!(".smoothing(rume::SmoothingCurve::{})", curve)
+            }
+            GraphInputOption::Events(_) | GraphInputOption::Stream(_) | GraphInputOption::None => {
+                String::new()
+            }
         }
     }
 }
@@ -303,8 +549,14 @@ impl ParsableDecl for GraphInputOption {
             "kind" => self.kind(tokens.by_ref()),
             "range" => self.range(tokens.by_ref()),
             "init" => self.init(tokens.by_ref()),
+            "taper" => self.taper(tokens.by_ref()),
             "smooth" => self.smooth(tokens.by_ref()),
-            _ => panic!("Unsupported input endpoint options: {}", name.as_str()),
+            "smoothing" => self.smoothing(tokens.by_ref()),
+            "events" => self.events(tokens.by_ref()),
+            _ => match StreamOption::parse(&name, tokens.by_ref()) {
+                Some(option) => *self = GraphInputOption::Stream(option),
+                None => panic!("Unsupported input endpoint options: {}", name.as_str()),
+            },
         }
     }
 }
@@ -339,11 +591,48 @@ impl GraphInput {
         }
     }
 
+    pub fn stream(&self) -> StreamDecl {
+        let options = self.options.iter().flat_map(|options| &options.inner);
+        let stream_options = options.filter_map(|option| match option {
+            GraphInputOption::Stream(option) => Some(option),
+            _ => None,
+        });
+
+        StreamDecl::new(stream_options, "rume::INPUT_STREAM_CAPACITY")
+    }
+
+    /// The capacity of the event stream
+    /// of the endpoint, if it has one.
+    pub fn events(&self) -> Option<usize> {
+        let mut options = self.options.iter().flat_map(|options| &options.inner);
+        options.find_map(|option| match option {
+            GraphInputOption::Events(capacity) => Some(*capacity),
+            _ => None,
+        })
+    }
+
     pub fn to_endpoint_init(&self) -> String {
+        self.to_endpoint_init_with(self.events().is_some())
+    }
+
+    /// The endpoint standing behind the inlet of
+    /// the same name when the graph is built as a
+    /// subgraph. Its values come from the inlet,
+    /// so it has no events and its stream is idle.
+    pub fn to_inlet_endpoint_init(&self) -> String {
+        format!(
+            "let {name}_inlet = builder.input();\n\t\
+             let (_, {name}_consumer) = rume::make_input_endpoint();\n\t{}",
+            self.to_endpoint_init_with(false),
+            name = self.name
+        )
+    }
+
+    fn to_endpoint_init_with(&self, events: bool) -> String {
         let mut endpoint_init = String::new();
 
         endpoint_init.push_str(&format!(
-            "let {} = rume::make_processor(rume::InputEndpointBuilder::new({}_consumer)\n",
+            "let {} = builder.add(rume::InputEndpointBuilder::new({}_consumer)\n",
             self.name, self.name
         ));
 
@@ -353,9 +642,26 @@ impl GraphInput {
             }
         }
 
+        if events {
+            endpoint_init.push_str(&format!(
+                "\t\t.events({}_event_consumer, builder.clock())\n",
+                self.name
+            ));
+        }
+
         endpoint_init.push_str("\t\t.build());");
         endpoint_init
     }
+
+    pub fn to_events_init(&self) -> String {
+        match self.events() {
+            Some(capacity) => format!(
+                "let ({}_events, {}_event_consumer) = rume::make_event_stream({});",
+                self.name, self.name, capacity
+            ),
+            None => String::new(),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -369,11 +675,9 @@ impl GraphInputs {
 
         for decl in &self.decls {
             endpoints_init.push_str(&format!(
-                "\t{}\n\t{}\n\n",
-                format!(
-                    "let ({}_producer, {}_consumer) = rume::make_input_endpoint();",
-                    decl.name, decl.name
-                ),
+                "\t{}\n\t{}\n\t{}\n\n",
+                decl.stream().to_init(&decl.name, "input"),
+                decl.to_events_init(),
                 decl.to_endpoint_init()
             ));
         }
@@ -381,15 +685,54 @@ impl GraphInputs {
         endpoints_init
     }
 
+    /// The inlets feeding the endpoints when
+    /// the graph is built as a subgraph, so
+    /// their options still apply.
+    pub fn to_inlets_init(&self) -> String {
+        let mut inlets_init = String::new();
+
+        for decl in &self.decls {
+            inlets_init.push_str(&format!("\t{}\n\n", decl.to_inlet_endpoint_init()));
+        }
+
+        inlets_init
+    }
+
+    /// The connections from each inlet
+    /// to the endpoint it feeds.
+    pub fn to_inlet_connections(&self) -> String {
+        let mut connections = String::new();
+
+        for decl in &self.decls {
+            connections.push_str(&format!(
+                "\t\t.connection(
+                    \trume::OutputPort::of({name}_inlet, 0, |proc| &proc.output),
+                    \trume::InputPort::of({name}, 1, |proc| &proc.signal)
+                )\n",
+                name = decl.name
+            ));
+        }
+
+        connections
+    }
+
     pub fn to_struct_decl(&self) -> String {
         let mut struct_decl = String::new();
         struct_decl.push_str("\npub struct Inputs {\n");
 
         for decl in &self.decls {
             struct_decl.push_str(&format!(
-                "\tpub {}: rume::InputStreamProducer,\n",
-                decl.name
+                "\tpub {}: rume::InputStreamProducer<{}>,\n",
+                decl.name,
+                decl.stream().sample
             ));
+
+            if decl.events().is_some() {
+                struct_decl.push_str(&format!(
+                    "\tpub {}_events: rume::EventProducer,\n",
+                    decl.name
+                ));
+            }
         }
 
         struct_decl.push_str("}\n");
@@ -402,6 +745,10 @@ impl GraphInputs {
 
         for decl in &self.decls {
             struct_init.push_str(&format!("{}: {}_producer, ", decl.name, decl.name));
+
+            if decl.events().is_some() {
+                struct_init.push_str(&format!("{}_events, ", decl.name));
+            }
         }
 
         struct_init.push('}');
@@ -439,25 +786,114 @@ impl ParsableDecl for GraphInputs {
     }
 }
 
+#[derive(Debug, Default)]
+struct GraphOutputOptions {
+    inner: Vec<StreamOption>,
+    channels: Option<String>,
+    meter: Option<usize>,
+}
+
+impl ParsableDecl for GraphOutputOptions {
+    fn parse(&mut self, tokens: &mut IntoIter) {
+        loop {
+            let name = tokens
+                .by_ref()
+                .next()
+                .expect("Expected a name for this output option")
+                .to_string();
+
+            let _colon = tokens
+                .by_ref()
+                .next()
+                .expect("Expected ':' character after an output option name");
+
+            if name == "channels" {
+                self.channels = Some(
+                    tokens
+                        .by_ref()
+                        .next()
+                        .expect("Expected a number of channels or 'config'")
+                        .to_string(),
+                );
+            } else if name == "meter" {
+                self.meter = Some(
+                    tokens
+                        .by_ref()
+                        .next()
+                        .expect("Expected a meter window, in samples")
+                        .to_string()
+                        .parse::<usize>()
+                        .expect("Failed to parse meter window"),
+                );
+            } else {
+                match StreamOption::parse(&name, tokens.by_ref()) {
+                    Some(option) => self.inner.push(option),
+                    None => panic!("Unsupported output endpoint options: {}", name.as_str()),
+                }
+            }
+
+            if tokens.by_ref().next().is_none() {
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct GraphOutput {
+    name: String,
+    options: GraphOutputOptions,
+}
+
+impl GraphOutput {
+    pub fn stream(&self) -> StreamDecl {
+        StreamDecl::new(self.options.inner.iter(), "rume::OUTPUT_STREAM_CAPACITY")
+    }
+
+    /// The endpoint streams as many channels as the
+    /// `AudioConfig` has with `channels: config`.
+    pub fn to_channels_init(&self) -> String {
+        match self.options.channels.as_deref() {
+            None => String::new(),
+            Some("config") => ".with_config_channels()".to_string(),
+            Some(channels) => format!(
+                ".with_channels({})",
+                channels
+                    .parse::<usize>()
+                    .expect("Failed to parse output endpoint channels")
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 struct GraphOutputs {
-    names: Vec<String>,
+    decls: Vec<GraphOutput>,
 }
 
 impl GraphOutputs {
     pub fn to_endpoints_init(&self) -> String {
         let mut endpoints_init = String::new();
 
-        for name in &self.names {
+        for decl in &self.decls {
+            if let Some(window) = decl.options.meter {
+                endpoints_init.push_str(&format!(
+                    "\tlet ({name}_writer, {name}_consumer) = rume::make_meter_endpoint();\n\t\
+                     let {name} = builder.add(rume::MeterEndpoint::new({name}_writer).with_window({window}));\n\n",
+                    name = decl.name,
+                    window = window
+                ));
+                continue;
+            }
+
             endpoints_init.push_str(&format!(
                 "\t{}\n\t{}\n\n",
+                decl.stream().to_init(&decl.name, "output"),
                 format!(
-                    "let ({}_producer, {}_consumer) = rume::make_output_endpoint();",
-                    name, name
-                ),
-                format!(
-                    "let {} = rume::make_processor(rume::OutputEndpoint::new({}_producer));",
-                    name, name
+                    "let {} = builder.add(rume::OutputEndpoint::new({}_producer){});",
+                    decl.name,
+                    decl.name,
+                    decl.to_channels_init()
                 )
             ));
         }
@@ -465,12 +901,41 @@ impl GraphOutputs {
         endpoints_init
     }
 
+    /// Whether every output is a mono stream,
+    /// which subgraph outlets have to be.
+    pub fn are_mono_streams(&self) -> bool {
+        self.decls
+            .iter()
+            .all(|decl| decl.options.channels.is_none() && decl.options.meter.is_none())
+    }
+
+    /// The outlets standing in for the endpoints
+    /// when the graph is built as a subgraph.
+    pub fn to_outlets_init(&self) -> String {
+        let mut outlets_init = String::new();
+
+        for decl in &self.decls {
+            outlets_init.push_str(&format!("\tlet {} = builder.output();\n", decl.name));
+        }
+
+        outlets_init
+    }
+
     pub fn to_struct_decl(&self) -> String {
         let mut struct_decl = String::new();
         struct_decl.push_str("\npub struct Outputs {\n");
 
-        for name in &self.names {
-            struct_decl.push_str(&format!("\tpub {}: rume::OutputStreamConsumer,\n", name));
+        for decl in &self.decls {
+            match decl.options.meter {
+                Some(_) => {
+                    struct_decl.push_str(&format!("\tpub {}: rume::MeterReader,\n", decl.name))
+                }
+                None => struct_decl.push_str(&format!(
+                    "\tpub {}: rume::OutputStreamConsumer<{}>,\n",
+                    decl.name,
+                    decl.stream().sample
+                )),
+            }
         }
 
         struct_decl.push_str("}\n");
@@ -481,8 +946,8 @@ impl GraphOutputs {
         let mut struct_init = String::new();
         struct_init.push_str("Outputs {");
 
-        for name in &self.names {
-            struct_init.push_str(&format!("{}: {}_consumer, ", name, name));
+        for decl in &self.decls {
+            struct_init.push_str(&format!("{}: {}_consumer, ", decl.name, decl.name));
         }
 
         struct_init.push('}');
@@ -501,12 +966,25 @@ impl ParsableDecl for GraphOutputs {
                 .expect("Expected a name for this output")
                 .to_string();
 
-            self.names.push(name);
+            let mut output = GraphOutput {
+                name,
+                ..Default::default()
+            };
 
-            let _comma = tokens
+            let character = tokens
                 .by_ref()
                 .next()
                 .expect("Expected ',' character after an output name");
+
+            if character.to_string() == ":" {
+                let mut raw_opts =
+                    extract_group(tokens, "expected a group of output endpoint options");
+                output.options = GraphOutputOptions::new(raw_opts.by_ref());
+
+                let _comma = tokens.by_ref().next();
+            }
+
+            self.decls.push(output);
         }
     }
 }
@@ -539,48 +1017,60 @@ impl ParsableDecl for GraphDecl {
     }
 }
 
+impl GraphDecl {
+    /// Build the graph as a `rume::Subgraph`, with
+    /// its endpoints as the ports of the subgraph.
+    /// Each input endpoint is fed by an inlet.
+    fn to_subgraph_fns(&self) -> String {
+        let mut subgraph_fns = String::new();
+
+        subgraph_fns
+            .push_str("\npub fn try_subgraph() -> Result<rume::Subgraph, rume::GraphError> {\n");
+
+        subgraph_fns.push_str("\tlet mut builder = rume::SubgraphBuilder::default();\n");
+        subgraph_fns.push_str(&self.inputs.to_inlets_init());
+        subgraph_fns.push_str(&self.outputs.to_outlets_init());
+        subgraph_fns.push_str(&self.processors.to_processors_init());
+
+        subgraph_fns.push_str("\n\tbuilder\n");
+        subgraph_fns.push_str(&self.inputs.to_inlet_connections());
+        subgraph_fns.push_str(&self.connections.to_connections());
+        subgraph_fns.push_str("\t\t.try_build()\n}\n");
+
+        subgraph_fns.push_str(
+            "
+pub fn subgraph() -> rume::Subgraph {
+    match try_subgraph() {
+        Ok(subgraph) => subgraph,
+        Err(error) => panic!(\"{}\", error),
+    }
+}
+",
+        );
+
+        subgraph_fns
+    }
+}
+
 impl ToString for GraphDecl {
     fn to_string(&self) -> String {
         let mut build_graph_fn = String::new();
 
-        build_graph_fn.push_str("pub fn build() -> (rume::SignalChain, Inputs, Outputs) {\n");
+        build_graph_fn.push_str(
+            "pub fn try_build() -> Result<(rume::SignalChain, Inputs, Outputs), rume::GraphError> {\n",
+        );
 
+        build_graph_fn.push_str("\tlet mut builder = rume::SignalChainBuilder::default();\n");
         build_graph_fn.push_str(&self.inputs.to_endpoints_init());
         build_graph_fn.push_str(&self.outputs.to_endpoints_init());
         build_graph_fn.push_str(&self.processors.to_processors_init());
 
-        build_graph_fn.push_str("\n\tlet chain = rume::SignalChainBuilder::default()\n");
-
-        for decl in &self.inputs.decls {
-            build_graph_fn.push_str(&format!("\t\t.processor({}.clone())\n", decl.name));
-        }
-
-        for name in &self.outputs.names {
-            build_graph_fn.push_str(&format!("\t\t.processor({}.clone())\n", name));
-        }
+        build_graph_fn.push_str("\n\tlet chain = builder\n");
 
-        for decl in &self.processors.decls {
-            build_graph_fn.push_str(&format!("\t\t.processor({}.clone())\n", decl.name));
-        }
-
-        for decl in &self.connections.decls {
-            build_graph_fn.push_str(&format!(
-                "\t\t.connection(
-                    \trume::OutputPort {{ proc: {}.clone(), port: Box::new({}.clone().borrow(){}.clone()) }},
-                    \trume::InputPort {{ proc: {}.clone(), port: Box::new({}.clone().borrow(){}.clone()) }}
-                )\n",
-                decl.tx_processor,
-                decl.tx_processor,
-                decl.tx_port,
-                decl.rx_processor,
-                decl.rx_processor,
-                decl.rx_port,
-            ));
-        }
-
-        build_graph_fn.push_str("\t\t.build();\n\n");
+        build_graph_fn.push_str(&self.connections.to_connections());
+        build_graph_fn.push_str("\t\t.try_build()?;\n\n");
         build_graph_fn.push_str(&format!(
-            "\t( chain, {}, {} )",
+            "\tOk(( chain, {}, {} ))",
             self.inputs.to_struct_init(),
             self.outputs.to_struct_init(),
         ));
@@ -588,6 +1078,21 @@ impl ToString for GraphDecl {
         build_graph_fn.push('}');
         build_graph_fn.push('\n');
 
+        build_graph_fn.push_str(
+            "
+pub fn build() -> (rume::SignalChain, Inputs, Outputs) {
+    match try_build() {
+        Ok(graph) => graph,
+        Err(error) => panic!(\"{}\", error),
+    }
+}
+",
+        );
+
+        if self.outputs.are_mono_streams() {
+            build_graph_fn.push_str(&self.to_subgraph_fns());
+        }
+
         let input_struct_decl = format!("\t{}\n", self.inputs.to_struct_decl());
         let output_struct_decl = format!("\t{}\n", self.outputs.to_struct_decl());
 
@@ -51,6 +51,34 @@ pub fn processor(_attr: TokenStream, item: TokenStream) -> TokenStream {
         fields.push(field.clone());
     }
 
+    // A port tuple of one is the port itself,
+    // so only larger tuples are indexed.
+    let input_arms: Vec<_> = (0..inputs.len())
+        .map(|i| {
+            let field = match inputs.len() {
+                1 => quote! { &proc.input },
+                _ => {
+                    let index = syn::Index::from(i);
+                    quote! { &proc.input.#index }
+                }
+            };
+            quote! { #i => Some(InputPort::of(processor, #i, |proc| #field)), }
+        })
+        .collect();
+
+    let output_arms: Vec<_> = (0..outputs.len())
+        .map(|i| {
+            let field = match outputs.len() {
+                1 => quote! { &proc.output },
+                _ => {
+                    let index = syn::Index::from(i);
+                    quote! { &proc.output.#index }
+                }
+            };
+            quote! { #i => Some(OutputPort::of(processor, #i, |proc| #field)), }
+        })
+        .collect();
+
     (quote! {
         #[derive(Debug, Default, Clone)]
         pub struct #processor {
@@ -61,23 +89,36 @@ pub fn processor(_attr: TokenStream, item: TokenStream) -> TokenStream {
         #(
             #[derive(Debug, Default, Clone)]
             pub struct #input_enums;
-            impl Input<dyn Processor + 'static> for #input_enums {
-                fn set(&self, this: SharedProc<dyn Processor + 'static>, value: f32) {
-                    let processor = unsafe {&mut (*(this.as_ptr() as *mut #processor)) };
-                    processor.#inputs = value;
+            impl Input<#processor> for #input_enums {
+                fn set(&self, this: &mut #processor, value: f32) {
+                    this.#inputs = value;
                 }
             }
         )*
         #(
             #[derive(Debug, Default, Clone)]
             pub struct #output_enums;
-            impl Output<dyn Processor + 'static> for #output_enums {
-                fn get(&self, this: SharedProc<dyn Processor + 'static>) -> f32 {
-                    let processor = unsafe {&mut (*(this.as_ptr() as *mut #processor)) };
-                    processor.#outputs
+            impl Output<#processor> for #output_enums {
+                fn get(&self, this: &mut #processor) -> f32 {
+                    this.#outputs
                 }
             }
         )*
+        impl IndexedPorts for #processor {
+            fn input_port(processor: Handle<Self>, index: usize) -> Option<InputPort> {
+                match index {
+                    #(#input_arms)*
+                    _ => None,
+                }
+            }
+
+            fn output_port(processor: Handle<Self>, index: usize) -> Option<OutputPort> {
+                match index {
+                    #(#output_arms)*
+                    _ => None,
+                }
+            }
+        }
     })
     .into()
 }